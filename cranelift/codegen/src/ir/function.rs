@@ -96,6 +96,15 @@ pub struct Function {
     /// are saved in the frame. This information is created during the prologue and epilogue
     /// passes.
     pub frame_layout: Option<FrameLayout>,
+
+    /// An optional global value which represents an expression evaluating to
+    /// the stack limit for this function.
+    ///
+    /// When present, the prologue of the function checks that the stack
+    /// pointer doesn't go below this value, trapping with
+    /// `TrapCode::StackOverflow` otherwise. This is an alternative to
+    /// passing the limit as an `ArgumentPurpose::StackLimit` parameter.
+    pub stack_limit: Option<ir::GlobalValue>,
 }
 
 impl Function {
@@ -120,6 +129,7 @@ impl Function {
             srclocs: SecondaryMap::new(),
             prologue_end: None,
             frame_layout: None,
+            stack_limit: None,
         }
     }
 
@@ -141,6 +151,7 @@ impl Function {
         self.srclocs.clear();
         self.prologue_end = None;
         self.frame_layout = None;
+        self.stack_limit = None;
     }
 
     /// Create a new empty, anonymous function with a Fast calling convention.
@@ -20,7 +20,7 @@ use crate::ir::{
 };
 use crate::isa::{CallConv, RegClass, RegUnit, TargetIsa};
 use crate::regalloc::RegisterSet;
-use crate::result::CodegenResult;
+use crate::result::{CodegenError, CodegenResult};
 use crate::stack_layout::layout_stack;
 use alloc::borrow::Cow;
 use core::i32;
@@ -554,7 +554,7 @@ fn fastcall_prologue_epilogue(func: &mut ir::Function, isa: &dyn TargetIsa) -> C
     let entry_block = func.layout.entry_block().expect("missing entry block");
     let mut pos = EncCursor::new(func, isa).at_first_insertion_point(entry_block);
     let prologue_cfa_state =
-        insert_common_prologue(&mut pos, local_stack_size, reg_type, &csrs, isa);
+        insert_common_prologue(&mut pos, local_stack_size, reg_type, &csrs, isa)?;
 
     // Reset the cursor and insert the epilogue
     let mut pos = pos.at_position(CursorPosition::Nowhere);
@@ -615,7 +615,7 @@ fn system_v_prologue_epilogue(func: &mut ir::Function, isa: &dyn TargetIsa) -> C
     let entry_block = func.layout.entry_block().expect("missing entry block");
     let mut pos = EncCursor::new(func, isa).at_first_insertion_point(entry_block);
     let prologue_cfa_state =
-        insert_common_prologue(&mut pos, local_stack_size, reg_type, &csrs, isa);
+        insert_common_prologue(&mut pos, local_stack_size, reg_type, &csrs, isa)?;
 
     // Reset the cursor and insert the epilogue
     let mut pos = pos.at_position(CursorPosition::Nowhere);
@@ -639,7 +639,7 @@ fn insert_common_prologue(
     reg_type: ir::types::Type,
     csrs: &RegisterSet,
     isa: &dyn TargetIsa,
-) -> Option<CFAState> {
+) -> CodegenResult<Option<CFAState>> {
     let word_size = isa.pointer_bytes() as isize;
     if stack_size > 0 {
         // Check if there is a special stack limit parameter. If so insert stack check.
@@ -655,6 +655,25 @@ fn insert_common_prologue(
         }
     }
 
+    // Otherwise check if the stack limit is given as a global value. Unlike the
+    // special parameter above this is also checked in non-leaf functions
+    // without any locals, since each call through them still pushes a return
+    // address and frame pointer, and unbounded recursion through such
+    // functions needs to be caught too.
+    let has_stack_limit_arg = pos
+        .func
+        .special_param(ArgumentPurpose::StackLimit)
+        .is_some();
+    if let Some(gv) = pos.func.stack_limit {
+        if !has_stack_limit_arg && (stack_size > 0 || !pos.func.is_leaf()) {
+            let scratch = ir::ValueLoc::Reg(RU::rax as RegUnit);
+            let stack_limit = interpret_gv(pos, gv, scratch)?;
+            let total_stack_size =
+                (csrs.iter(GPR).len() + 1 + 1) as i64 * word_size as i64 + stack_size;
+            insert_stack_check(pos, total_stack_size, stack_limit);
+        }
+    }
+
     let mut cfa_state = if let Some(ref mut frame_layout) = pos.func.frame_layout {
         let cfa_state = CFAState {
             cf_ptr_reg: RU::rsp as RegUnit,
@@ -796,7 +815,7 @@ fn insert_common_prologue(
         }
     }
 
-    cfa_state
+    Ok(cfa_state)
 }
 
 /// Insert a check that generates a trap if the stack pointer goes
@@ -804,10 +823,16 @@ fn insert_common_prologue(
 fn insert_stack_check(pos: &mut EncCursor, stack_size: i64, stack_limit_arg: ir::Value) {
     use crate::ir::condcodes::IntCC;
 
-    // Copy `stack_limit_arg` into a %rax and use it for calculating
-    // a SP threshold.
-    let stack_limit_copy = pos.ins().copy(stack_limit_arg);
-    pos.func.locations[stack_limit_copy] = ir::ValueLoc::Reg(RU::rax as RegUnit);
+    // Copy `stack_limit_arg` into a %rax, unless it's already there, and use
+    // it for calculating a SP threshold.
+    let rax = ir::ValueLoc::Reg(RU::rax as RegUnit);
+    let stack_limit_copy = if pos.func.locations[stack_limit_arg] == rax {
+        stack_limit_arg
+    } else {
+        let copy = pos.ins().copy(stack_limit_arg);
+        pos.func.locations[copy] = rax;
+        copy
+    };
     let sp_threshold = pos.ins().iadd_imm(stack_limit_copy, stack_size);
     pos.func.locations[sp_threshold] = ir::ValueLoc::Reg(RU::rax as RegUnit);
 
@@ -822,6 +847,44 @@ fn insert_stack_check(pos: &mut EncCursor, stack_size: i64, stack_limit_arg: ir:
     );
 }
 
+/// Emit instructions computing the global value `gv` at the current position,
+/// for use in the prologue of a function.
+///
+/// Register allocation has already run by the time the prologue is inserted,
+/// so every intermediate value is placed in the `scratch` location. Only
+/// `vmctx` and loads based on it are supported.
+fn interpret_gv(
+    pos: &mut EncCursor,
+    gv: ir::GlobalValue,
+    scratch: ir::ValueLoc,
+) -> CodegenResult<ir::Value> {
+    match pos.func.global_values[gv] {
+        ir::GlobalValueData::VMContext => pos
+            .func
+            .special_param(ArgumentPurpose::VMContext)
+            .ok_or_else(|| {
+                CodegenError::Unsupported("stack limit requires a vmctx parameter".to_string())
+            }),
+        ir::GlobalValueData::Load {
+            base,
+            offset,
+            global_type,
+            readonly: _,
+        } => {
+            let base = interpret_gv(pos, base, scratch)?;
+            let ret = pos
+                .ins()
+                .load(global_type, ir::MemFlags::trusted(), base, offset);
+            pos.func.locations[ret] = scratch;
+            Ok(ret)
+        }
+        ref other => Err(CodegenError::Unsupported(format!(
+            "global value for stack limit not supported: {}",
+            other
+        ))),
+    }
+}
+
 /// Find all `return` instructions and insert epilogues before them.
 fn insert_common_epilogues(
     pos: &mut EncCursor,
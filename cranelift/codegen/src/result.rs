@@ -30,6 +30,12 @@ pub enum CodegenError {
     /// is exceeded, compilation fails.
     #[error("Code for function is too large")]
     CodeTooLarge,
+
+    /// Something is not supported by the code generator. This might be an indication that a
+    /// feature is used without explicitly enabling it, or that something is temporarily
+    /// unsupported by a given target backend.
+    #[error("Unsupported feature: {0}")]
+    Unsupported(String),
 }
 
 /// A convenient alias for a `Result` that uses `CodegenError` as the error type.
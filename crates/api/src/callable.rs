@@ -1,11 +1,18 @@
+use crate::func::Caller;
 use crate::runtime::Store;
 use crate::trampoline::generate_func_export;
 use crate::trap::Trap;
-use crate::types::FuncType;
-use crate::values::Val;
+use crate::types::{FuncType, ValType};
+use crate::values::{Val, ValRef};
+use std::cell::RefCell;
 use std::cmp::max;
+use std::future::Future;
+use std::pin::Pin;
 use std::ptr;
 use std::rc::Rc;
+use std::task::{Context, Poll, RawWaker, RawWakerVTable, Waker};
+use std::thread::{self, Thread};
+use wasmtime_environ::ir;
 use wasmtime_runtime::{ExportFunction, InstanceHandle, VMTrampoline};
 
 /// A trait representing a function that can be imported and called from inside
@@ -83,11 +90,253 @@ pub trait Callable {
     /// `results` is mutable list of results to be potentially set by your
     /// function. Produces a `Trap` if the function encounters any errors.
     fn call(&self, params: &[Val], results: &mut [Val]) -> Result<(), Trap>;
+
+    /// What is called when the function is invoked from WebAssembly, along
+    /// with a [`Caller`] describing the instance which made the call.
+    ///
+    /// Override this method to access the caller's exports, for example to
+    /// read a string argument out of the caller's linear memory. By default
+    /// this ignores `caller` and delegates to [`Callable::call`].
+    fn call_with_caller(
+        &self,
+        _caller: Caller<'_>,
+        params: &[Val],
+        results: &mut [Val],
+    ) -> Result<(), Trap> {
+        self.call(params, results)
+    }
+
+    /// Invokes this function once for each `(params, results)` pair in
+    /// `batches`, stopping at the first trap.
+    ///
+    /// By default this simply calls [`Callable::call`] in a loop, but it may
+    /// be overridden to amortize any setup shared between invocations.
+    fn call_batch(&self, batches: &mut [(&[Val], &mut [Val])]) -> Result<(), Trap> {
+        for (params, results) in batches.iter_mut() {
+            self.call(params, results)?;
+        }
+        Ok(())
+    }
+
+    /// Like [`Callable::call`], but with borrowed parameters.
+    ///
+    /// This is what [`AdapterFunc::call_ref`](crate::AdapterFunc::call_ref)
+    /// invokes. By default the parameters are copied into owned values and
+    /// passed to [`Callable::call`]; implementations which can consume
+    /// borrowed strings directly may override this to avoid the allocations.
+    fn call_ref(&self, params: &[ValRef<'_>], results: &mut [Val]) -> Result<(), Trap> {
+        let params = params.iter().map(ValRef::to_val).collect::<Vec<_>>();
+        self.call(&params, results)
+    }
+}
+
+/// A variant of [`Callable`] for host functions which produce their results
+/// as an owned `Vec` rather than writing them into a pre-sized slice.
+///
+/// This is often more convenient for dynamic implementations. Wrap an
+/// implementation in an [`OwnedCallable`] to use it anywhere a [`Callable`] is
+/// expected, for example with [`Func::new`](crate::Func::new).
+pub trait CallableOwned {
+    /// What is called when the function is invoked in WebAssembly.
+    /// `params` is an immutable list of parameters provided to the function,
+    /// and on success the function's results are returned. Produces a `Trap`
+    /// if the function encounters any errors.
+    fn call(&self, params: &[Val]) -> Result<Vec<Val>, Trap>;
+}
+
+/// Adapts a [`CallableOwned`] into a [`Callable`] for a given [`FuncType`].
+///
+/// The results returned by the wrapped function are checked against the
+/// results of the `FuncType`, and a `Trap` is produced if they don't match.
+pub struct OwnedCallable<T> {
+    ty: FuncType,
+    callable: T,
+}
+
+impl<T: CallableOwned> OwnedCallable<T> {
+    /// Creates a new [`Callable`] invoking `callable`, whose results must
+    /// match those of `ty`.
+    pub fn new(ty: FuncType, callable: T) -> OwnedCallable<T> {
+        OwnedCallable { ty, callable }
+    }
+}
+
+impl<T: CallableOwned> Callable for OwnedCallable<T> {
+    fn call(&self, params: &[Val], results: &mut [Val]) -> Result<(), Trap> {
+        let values = self.callable.call(params)?;
+        let expected = self.ty.results();
+        if values.len() != expected.len() {
+            return Err(Trap::new(format!(
+                "expected {} results, got {}",
+                expected.len(),
+                values.len()
+            )));
+        }
+        for (val, ty) in values.iter().zip(expected) {
+            if val.ty() != *ty && !(ty.is_ref() && val.is_null()) {
+                return Err(Trap::new(format!(
+                    "expected result of type {}, got {}",
+                    ty,
+                    val.ty()
+                )));
+            }
+        }
+        for (slot, val) in results.iter_mut().zip(values) {
+            *slot = val;
+        }
+        Ok(())
+    }
+}
+
+/// A variant of [`Callable`] for host functions which need to `.await`, for
+/// example to perform asynchronous I/O.
+///
+/// Use [`Func::new_async`](crate::Func::new_async) to create a function from
+/// an implementation of this trait.
+pub trait AsyncCallable {
+    /// What is called when the function is invoked in WebAssembly, like
+    /// [`Callable::call`]. The returned future is driven to completion before
+    /// the call returns to WebAssembly.
+    fn call<'a>(
+        &'a self,
+        params: &'a [Val],
+        results: &'a mut [Val],
+    ) -> Pin<Box<dyn Future<Output = Result<(), Trap>> + 'a>>;
+}
+
+/// A [`Callable`] which blocks the calling thread on the future produced by an
+/// [`AsyncCallable`].
+pub(crate) struct BlockingCallable {
+    callable: Rc<dyn AsyncCallable + 'static>,
+}
+
+impl BlockingCallable {
+    pub(crate) fn new(callable: Rc<dyn AsyncCallable + 'static>) -> BlockingCallable {
+        BlockingCallable { callable }
+    }
+}
+
+impl Callable for BlockingCallable {
+    fn call(&self, params: &[Val], results: &mut [Val]) -> Result<(), Trap> {
+        block_on(self.callable.call(params, results))
+    }
+}
+
+/// Polls `future` to completion on the current thread, parking the thread
+/// while the future is pending until it's woken.
+fn block_on<F: Future>(future: F) -> F::Output {
+    // The waker's data is a boxed handle to the thread to unpark.
+    unsafe fn clone(data: *const ()) -> RawWaker {
+        let thread = (*(data as *const Thread)).clone();
+        RawWaker::new(Box::into_raw(Box::new(thread)) as *const (), &VTABLE)
+    }
+    unsafe fn wake(data: *const ()) {
+        Box::from_raw(data as *mut Thread).unpark();
+    }
+    unsafe fn wake_by_ref(data: *const ()) {
+        (*(data as *const Thread)).unpark();
+    }
+    unsafe fn drop_waker(data: *const ()) {
+        drop(Box::from_raw(data as *mut Thread));
+    }
+    static VTABLE: RawWakerVTable = RawWakerVTable::new(clone, wake, wake_by_ref, drop_waker);
+
+    let thread = Box::new(thread::current());
+    let raw = RawWaker::new(Box::into_raw(thread) as *const (), &VTABLE);
+    let waker = unsafe { Waker::from_raw(raw) };
+    let mut cx = Context::from_waker(&waker);
+    let mut future = Box::pin(future);
+    loop {
+        match future.as_mut().poll(&mut cx) {
+            Poll::Ready(output) => return output,
+            Poll::Pending => thread::park(),
+        }
+    }
+}
+
+/// A [`Callable`] backed by a closure which may mutate its captured state.
+///
+/// The closure is kept in a `RefCell`, so a reentrant call, made while the
+/// closure is already running, produces a `Trap` instead of aliasing the
+/// closure's state.
+pub(crate) struct MutCallable<F> {
+    func: RefCell<F>,
+}
+
+impl<F> MutCallable<F>
+where
+    F: FnMut(&[Val], &mut [Val]) -> Result<(), Trap>,
+{
+    pub(crate) fn new(func: F) -> MutCallable<F> {
+        MutCallable {
+            func: RefCell::new(func),
+        }
+    }
+}
+
+impl<F> Callable for MutCallable<F>
+where
+    F: FnMut(&[Val], &mut [Val]) -> Result<(), Trap>,
+{
+    fn call(&self, params: &[Val], results: &mut [Val]) -> Result<(), Trap> {
+        let mut func = self
+            .func
+            .try_borrow_mut()
+            .map_err(|_| Trap::new("host function called reentrantly"))?;
+        (*func)(params, results)
+    }
+}
+
+/// A [`Callable`] which ignores its parameters and returns the default value
+/// of each of its result types.
+pub(crate) struct NoopCallable {
+    results: Box<[ValType]>,
+}
+
+impl NoopCallable {
+    pub(crate) fn new(ty: &FuncType) -> NoopCallable {
+        NoopCallable {
+            results: ty.results().into(),
+        }
+    }
+}
+
+impl Callable for NoopCallable {
+    fn call(&self, _params: &[Val], results: &mut [Val]) -> Result<(), Trap> {
+        for (slot, ty) in results.iter_mut().zip(self.results.iter()) {
+            *slot = Val::default_for(ty);
+        }
+        Ok(())
+    }
+}
+
+/// A [`Callable`] which returns its parameters as its results.
+pub(crate) struct IdentityCallable;
+
+impl Callable for IdentityCallable {
+    fn call(&self, params: &[Val], results: &mut [Val]) -> Result<(), Trap> {
+        results.clone_from_slice(params);
+        Ok(())
+    }
 }
 
 pub trait WrappedCallable: Callable {
     fn wasmtime_handle(&self) -> &InstanceHandle;
     fn wasmtime_function(&self) -> &ExportFunction;
+    fn native_address(&self) -> Option<usize>;
+
+    /// Returns the host [`Callable`] implementing this function, if it was
+    /// created from one.
+    fn host_callable(&self) -> Option<&Rc<dyn Callable + 'static>>;
+
+    /// Like `Callable::call`, but without checking that `params` match the
+    /// signature of this function.
+    ///
+    /// # Safety
+    ///
+    /// `params` must match the parameter types of this function and `results`
+    /// must have one slot per result.
+    unsafe fn call_unchecked(&self, params: &[Val], results: &mut [Val]) -> Result<(), Trap>;
 }
 
 pub(crate) struct WasmtimeFn {
@@ -111,6 +360,48 @@ impl WasmtimeFn {
             trampoline,
         }
     }
+
+    /// Invokes this function through its trampoline, assuming `params` and
+    /// `results` have already been checked against `signature`.
+    unsafe fn call_with_signature(
+        &self,
+        signature: &ir::Signature,
+        params: &[Val],
+        results: &mut [Val],
+    ) -> Result<(), Trap> {
+        let f = self.wasmtime_function();
+        // Every slot in `values_vec` is 128 bits wide, matching the value size
+        // that trampolines are generated with, so arguments and results of
+        // any type, including `v128`, each occupy exactly one slot.
+        let mut values_vec = vec![0i128; max(params.len(), results.len())];
+
+        // Store the argument values into `values_vec`.
+        for (arg, slot) in params.iter().zip(&mut values_vec) {
+            arg.write_value_to(slot);
+        }
+
+        // Call the trampoline.
+        if let Err(error) = wasmtime_runtime::wasmtime_call_trampoline(
+            f.vmctx,
+            ptr::null_mut(),
+            self.trampoline,
+            f.address,
+            values_vec.as_mut_ptr() as *mut u8,
+        ) {
+            let trap = Trap::from_jit(error);
+            self.store.report_trap(&trap);
+            return Err(trap);
+        }
+
+        // Load the return values out of `values_vec`.
+        for (index, abi_param) in signature.returns.iter().enumerate() {
+            let ptr = values_vec.as_ptr().add(index);
+
+            results[index] = Val::read_value_from(ptr, abi_param.value_type);
+        }
+
+        Ok(())
+    }
 }
 
 impl Callable for WasmtimeFn {
@@ -137,42 +428,14 @@ impl Callable for WasmtimeFn {
             )));
         }
 
-        let mut values_vec = vec![0; max(params.len(), results.len())];
-
-        // Store the argument values into `values_vec`.
         let param_tys = signature.params.iter().skip(2);
-        for ((arg, slot), ty) in params.iter().zip(&mut values_vec).zip(param_tys) {
+        for (arg, ty) in params.iter().zip(param_tys) {
             if arg.ty().get_wasmtime_type() != Some(ty.value_type) {
                 return Err(Trap::new("argument type mismatch"));
             }
-            unsafe {
-                arg.write_value_to(slot);
-            }
-        }
-
-        // Call the trampoline.
-        if let Err(error) = unsafe {
-            wasmtime_runtime::wasmtime_call_trampoline(
-                f.vmctx,
-                ptr::null_mut(),
-                self.trampoline,
-                f.address,
-                values_vec.as_mut_ptr() as *mut u8,
-            )
-        } {
-            return Err(Trap::from_jit(error));
-        }
-
-        // Load the return values out of `values_vec`.
-        for (index, abi_param) in signature.returns.iter().enumerate() {
-            unsafe {
-                let ptr = values_vec.as_ptr().add(index);
-
-                results[index] = Val::read_value_from(ptr, abi_param.value_type);
-            }
         }
 
-        Ok(())
+        unsafe { self.call_with_signature(&signature, params, results) }
     }
 }
 
@@ -183,6 +446,21 @@ impl WrappedCallable for WasmtimeFn {
     fn wasmtime_function(&self) -> &ExportFunction {
         &self.export
     }
+    fn native_address(&self) -> Option<usize> {
+        Some(self.export.address as usize)
+    }
+    fn host_callable(&self) -> Option<&Rc<dyn Callable + 'static>> {
+        None
+    }
+    unsafe fn call_unchecked(&self, params: &[Val], results: &mut [Val]) -> Result<(), Trap> {
+        let signature = self
+            .store
+            .compiler()
+            .signatures()
+            .lookup(self.export.signature)
+            .expect("missing signature");
+        self.call_with_signature(&signature, params, results)
+    }
 }
 
 pub struct NativeCallable {
@@ -210,10 +488,24 @@ impl WrappedCallable for NativeCallable {
     fn wasmtime_function(&self) -> &ExportFunction {
         &self.export
     }
+    fn native_address(&self) -> Option<usize> {
+        // The code behind `export` is only a generated trampoline into the
+        // host's `Callable`, so there's no meaningful native address to report.
+        None
+    }
+    fn host_callable(&self) -> Option<&Rc<dyn Callable + 'static>> {
+        Some(&self.callable)
+    }
+    unsafe fn call_unchecked(&self, params: &[Val], results: &mut [Val]) -> Result<(), Trap> {
+        // Host functions are invoked directly, so there are no checks to skip.
+        self.call(params, results)
+    }
 }
 
 impl Callable for NativeCallable {
     fn call(&self, params: &[Val], results: &mut [Val]) -> Result<(), Trap> {
-        self.callable.call(params, results)
+        // Panics are reported as traps here just like they are when the host
+        // function is called from wasm, see `stub_fn`.
+        Trap::catch_panic(|| self.callable.call(params, results))?
     }
 }
\ No newline at end of file
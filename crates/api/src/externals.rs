@@ -1,12 +1,26 @@
 use crate::trampoline::{generate_global_export, generate_memory_export, generate_table_export};
 use crate::values::{from_checked_anyfunc, into_checked_anyfunc, Val};
+use crate::AnyRef;
+use crate::InstanceId;
 use crate::Mutability;
-use crate::{ExternType, GlobalType, MemoryType, TableType, ValType};
-use crate::{Func, AdapterFunc, Store};
+use crate::{ExternType, GlobalType, ImportType, Limits, MemoryType, TableType, ValType};
+use crate::{Func, AdapterFunc, Store, WasmTy};
 use anyhow::{anyhow, bail, Result};
+use std::any::Any;
+use std::cell::RefCell;
+use std::cmp;
+use std::convert::TryInto;
+use std::fmt;
+use std::io;
+use std::iter;
+use std::marker;
+use std::mem;
+use std::ops::{Deref, Range};
+use std::ptr;
+use std::rc::Rc;
 use std::slice;
 use wasmtime_environ::{ir, wasm};
-use wasmtime_runtime::{self as runtime, InstanceHandle};
+use wasmtime_runtime::{self as runtime, InstanceHandle, VMGlobalDefinition};
 
 // Externals
 
@@ -83,6 +97,56 @@ impl Extern {
         }
     }
 
+    /// Converts this external into the underlying `Func`, if it's a function.
+    ///
+    /// Returns `self` back as the error if this is not a function.
+    pub fn into_func(self) -> Result<Func, Extern> {
+        match self {
+            Extern::Func(func) => Ok(func),
+            other => Err(other),
+        }
+    }
+
+    /// Converts this external into the underlying `AdapterFunc`, if it's an adapter.
+    ///
+    /// Returns `self` back as the error if this is not an adapter.
+    pub fn into_adapter(self) -> Result<AdapterFunc, Extern> {
+        match self {
+            Extern::Adapter(adapter) => Ok(adapter),
+            other => Err(other),
+        }
+    }
+
+    /// Converts this external into the underlying `Global`, if it's a global.
+    ///
+    /// Returns `self` back as the error if this is not a global.
+    pub fn into_global(self) -> Result<Global, Extern> {
+        match self {
+            Extern::Global(global) => Ok(global),
+            other => Err(other),
+        }
+    }
+
+    /// Converts this external into the underlying `Table`, if it's a table.
+    ///
+    /// Returns `self` back as the error if this is not a table.
+    pub fn into_table(self) -> Result<Table, Extern> {
+        match self {
+            Extern::Table(table) => Ok(table),
+            other => Err(other),
+        }
+    }
+
+    /// Converts this external into the underlying `Memory`, if it's a memory.
+    ///
+    /// Returns `self` back as the error if this is not a memory.
+    pub fn into_memory(self) -> Result<Memory, Extern> {
+        match self {
+            Extern::Memory(memory) => Ok(memory),
+            other => Err(other),
+        }
+    }
+
     /// Returns the type associated with this `Extern`.
     pub fn ty(&self) -> ExternType {
         match self {
@@ -94,6 +158,89 @@ impl Extern {
         }
     }
 
+    /// Returns the id of the [`Instance`](crate::Instance) which defines this
+    /// item, or `None` if it was defined by the host.
+    ///
+    /// Functions, tables and memories which an instance imports and then
+    /// re-exports report the instance which originally defined them, whereas
+    /// re-exported globals report the re-exporting instance. Adapters report
+    /// the instance defining the core function they wrap, if any.
+    pub fn origin(&self) -> Option<InstanceId> {
+        let vmctx = match self {
+            Extern::Func(f) => f.wasmtime_function().vmctx,
+            Extern::Global(g) => g.wasmtime_export.vmctx,
+            Extern::Table(t) => t.wasmtime_export.vmctx,
+            Extern::Memory(m) => m.wasmtime_export.vmctx,
+            Extern::Adapter(a) => a.core_func()?.wasmtime_function().vmctx,
+        };
+        let handle = unsafe { InstanceHandle::from_vmctx(vmctx) };
+        InstanceId::of(&handle)
+    }
+
+    /// Checks whether this `Extern` can be used to satisfy `import`.
+    ///
+    /// Functions, globals and adapters must have exactly the type that
+    /// `import` declares. Tables and memories follow the usual subtyping
+    /// rules for their limits: they must currently be at least as large as the
+    /// declared minimum, and if a maximum is declared they must have a maximum
+    /// no larger than it.
+    ///
+    /// # Errors
+    ///
+    /// Returns a human-readable description of the mismatch if this `Extern`
+    /// doesn't match `import`.
+    pub fn matches_import(&self, import: &ImportType) -> Result<(), String> {
+        let mismatch = |expected: &dyn fmt::Debug, found: &dyn fmt::Debug| -> Result<(), String> {
+            Err(format!(
+                "import `{}::{}` expected {:?}, found {:?}",
+                import.module(),
+                import.name(),
+                expected,
+                found
+            ))
+        };
+        match (self, import.ty()) {
+            (Extern::Func(f), ExternType::Func(ty)) => {
+                if f.ty() == ty {
+                    Ok(())
+                } else {
+                    mismatch(ty, f.ty())
+                }
+            }
+            (Extern::Adapter(a), ExternType::Adapter(ty)) => {
+                if a.ty() == ty {
+                    Ok(())
+                } else {
+                    mismatch(ty, a.ty())
+                }
+            }
+            (Extern::Global(g), ExternType::Global(ty)) => {
+                if g.ty().content() == ty.content() && g.ty().mutability() == ty.mutability() {
+                    Ok(())
+                } else {
+                    mismatch(ty, g.ty())
+                }
+            }
+            (Extern::Table(t), ExternType::Table(ty)) => {
+                let actual = Limits::new(t.size(), t.ty().limits().max());
+                if t.ty().element() == ty.element() && limits_match(&actual, ty.limits()) {
+                    Ok(())
+                } else {
+                    mismatch(ty, &TableType::new(t.ty().element().clone(), actual))
+                }
+            }
+            (Extern::Memory(m), ExternType::Memory(ty)) => {
+                let actual = Limits::new(m.size(), m.ty().limits().max());
+                if limits_match(&actual, ty.limits()) {
+                    Ok(())
+                } else {
+                    mismatch(ty, &MemoryType::new(actual))
+                }
+            }
+            (_, ty) => mismatch(ty, &self.ty()),
+        }
+    }
+
     pub(crate) fn get_wasmtime_export(&self) -> Option<wasmtime_runtime::Export> {
         match self {
             Extern::Func(f) => Some(f.wasmtime_function().clone().into()),
@@ -137,6 +284,17 @@ impl Extern {
     }
 }
 
+fn limits_match(actual: &Limits, expected: &Limits) -> bool {
+    if actual.min() < expected.min() {
+        return false;
+    }
+    match (actual.max(), expected.max()) {
+        (_, None) => true,
+        (Some(actual), Some(expected)) => actual <= expected,
+        (None, Some(_)) => false,
+    }
+}
+
 impl From<Func> for Extern {
     fn from(r: Func) -> Self {
         Extern::Func(r)
@@ -182,6 +340,10 @@ pub struct Global {
     ty: GlobalType,
     wasmtime_export: wasmtime_runtime::ExportGlobal,
     wasmtime_handle: InstanceHandle,
+    // The current value of interface-types `string` globals, which can't be
+    // stored in the wasm global definition itself, since this owns the
+    // string's bytes.
+    host_value: Option<Rc<RefCell<Val>>>,
 }
 
 impl Global {
@@ -195,21 +357,88 @@ impl Global {
     /// # Errors
     ///
     /// Returns an error if the `ty` provided does not match the type of the
-    /// value `val`.
+    /// value `val`, or if `ty` is a `v128` global, which isn't supported yet.
     pub fn new(store: &Store, ty: GlobalType, val: Val) -> Result<Global> {
-        if !val.comes_from_same_store(store) {
+        if !global_val_from_store(&val, store) {
             bail!("cross-`Store` globals are not supported");
         }
+        if *ty.content() == ValType::V128 {
+            bail!("v128 globals are not yet supported");
+        }
         if val.ty() != *ty.content() {
             bail!("value provided does not match the type of this global");
         }
-        let (wasmtime_handle, wasmtime_export) = generate_global_export(store, &ty, val)?;
-        Ok(Global {
+        Global::new_unchecked(store, ty, val)
+    }
+
+    /// Creates a new numeric `global` of type `ty` whose storage is initialized
+    /// directly with the raw `bits` given.
+    ///
+    /// This is intended for restoring globals from previously saved state, for
+    /// example bits read with [`Global::read_u128`]. For `f32` and `f64`
+    /// globals `bits` is the IEEE 754 bit pattern, which is preserved exactly,
+    /// including NaN payloads.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `ty` isn't an `i32`, `i64`, `f32` or `f64` global,
+    /// or if `bits` doesn't fit in a 32-bit type.
+    pub fn from_bits(store: &Store, ty: GlobalType, bits: u64) -> Result<Global> {
+        let narrow = || -> Result<u32> {
+            if bits > u64::from(u32::max_value()) {
+                bail!("bits {:#x} do not fit in a {} global", bits, ty.content());
+            }
+            Ok(bits as u32)
+        };
+        let val = match ty.content() {
+            ValType::I32 => Val::I32(narrow()? as i32),
+            ValType::I64 => Val::I64(bits as i64),
+            ValType::F32 => Val::F32(narrow()?),
+            ValType::F64 => Val::F64(bits),
+            other => bail!("cannot create a {} global from raw bits", other),
+        };
+        Global::new_unchecked(store, ty, val)
+    }
+
+    /// Creates a new `v128` global whose storage is initialized with `bits`.
+    ///
+    /// This is the `v128` counterpart of [`Global::from_bits`], since
+    /// [`Global::new`] doesn't support `v128` globals yet. Once created, such
+    /// globals are read and written like any other, with [`Global::get`] and
+    /// [`Global::set`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `ty` isn't a `v128` global.
+    pub fn from_bits_u128(store: &Store, ty: GlobalType, bits: u128) -> Result<Global> {
+        if *ty.content() != ValType::V128 {
+            bail!("cannot create a {} global from 128 raw bits", ty.content());
+        }
+        Global::new_unchecked(store, ty, Val::V128(bits))
+    }
+
+    /// Creates a new global, assuming `val` has already been checked against
+    /// `ty` and `store`.
+    fn new_unchecked(store: &Store, ty: GlobalType, val: Val) -> Result<Global> {
+        let host_value = if *ty.content() == ValType::String {
+            Some(Rc::new(RefCell::new(val.clone())))
+        } else {
+            None
+        };
+        let (wasmtime_handle, wasmtime_export) = generate_global_export(store, &ty, val.clone())?;
+        let global = Global {
             store: store.clone(),
             ty,
             wasmtime_export,
             wasmtime_handle,
-        })
+            host_value,
+        };
+        // Reference-typed globals are created null, since wasm initializers
+        // can't refer to host objects.
+        if global.ty().content().is_ref() {
+            global.write(&val);
+        }
+        Ok(global)
     }
 
     /// Returns the underlying type of this `global`.
@@ -226,6 +455,12 @@ impl Global {
                 ValType::I64 => Val::from(*definition.as_i64()),
                 ValType::F32 => Val::F32(*definition.as_u32()),
                 ValType::F64 => Val::F64(*definition.as_u64()),
+                ValType::V128 => Val::V128(*definition.as_u128()),
+                ValType::AnyRef | ValType::FuncRef => match read_ref_bits(definition) {
+                    0 => Val::default_for(self.ty().content()),
+                    bits => self.store.global_refs().get(bits),
+                },
+                ValType::String => self.host_value().borrow().clone(),
                 _ => unimplemented!("Global::get for {:?}", self.ty().content()),
             }
         }
@@ -248,20 +483,159 @@ impl Global {
                 val.ty()
             );
         }
-        if !val.comes_from_same_store(&self.store) {
+        if !global_val_from_store(&val, &self.store) {
+            bail!("cross-`Store` values are not supported");
+        }
+        self.write(&val);
+        Ok(())
+    }
+
+    /// Reads the current value of this global, applies `f` to it, and stores
+    /// the result back into the global, returning the new value.
+    ///
+    /// This is a convenience for read-modify-write patterns such as
+    /// incrementing a counter stored in a mutable global.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if this global is not mutable, or if the value
+    /// returned by `f` does not have the same type as this global. In either
+    /// case the global is left unmodified.
+    pub fn modify<F>(&self, f: F) -> Result<Val>
+    where
+        F: FnOnce(Val) -> Val,
+    {
+        if self.ty().mutability() != Mutability::Var {
+            bail!("immutable global cannot be set");
+        }
+        let val = f(self.get());
+        if val.ty() != *self.ty().content() {
+            bail!(
+                "global of type {:?} cannot be set to {:?}",
+                self.ty().content(),
+                val.ty()
+            );
+        }
+        if !global_val_from_store(&val, &self.store) {
             bail!("cross-`Store` values are not supported");
         }
+        self.write(&val);
+        Ok(val)
+    }
+
+    /// Reads the raw 128 bits of this global's underlying storage.
+    ///
+    /// This is a low-level escape hatch which **bypasses type checking
+    /// entirely**: the bits are returned as-is regardless of this global's
+    /// declared content type. It's intended for embedders that need to
+    /// implement their own reinterpretation of a global's contents.
+    ///
+    /// For globals narrower than 128 bits only the low bytes of the result are
+    /// meaningful, and the remaining bytes are unspecified. For reference
+    /// globals the storage holds an opaque pointer-sized handle to the
+    /// reference, and for `string` globals it does not hold the value returned
+    /// by [`Global::get`] at all. Prefer [`Global::get`] or [`Global::typed`]
+    /// whenever they're applicable.
+    pub fn read_u128(&self) -> u128 {
+        unsafe { *(*self.wasmtime_export.definition).as_u128() }
+    }
+
+    /// Overwrites the raw 128 bits of this global's underlying storage with
+    /// `v`.
+    ///
+    /// Like [`Global::read_u128`] this **bypasses type checking entirely**:
+    /// `v` is written as-is regardless of this global's declared content type.
+    /// For globals narrower than 128 bits this also clobbers the bytes past the
+    /// end of the value, which are unused. Writing to a `string` global through
+    /// this method doesn't change the value returned by [`Global::get`], and
+    /// code running in wasm may observe inconsistent state afterwards. Prefer
+    /// [`Global::set`] or [`Global::typed`] whenever they're applicable.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if this global is not mutable, or if it's a reference
+    /// global, whose storage must always identify a live reference.
+    pub fn write_u128(&self, v: u128) -> Result<()> {
+        if self.ty().mutability() != Mutability::Var {
+            bail!("immutable global cannot be set");
+        }
+        if self.ty().content().is_ref() {
+            bail!("reference globals cannot be written as raw bits");
+        }
+        unsafe {
+            *(*self.wasmtime_export.definition).as_u128_mut() = v;
+        }
+        Ok(())
+    }
+
+    /// Writes `val` into this global's storage, assuming all type, store and
+    /// mutability checks have already been performed.
+    fn write(&self, val: &Val) {
         unsafe {
             let definition = &mut *self.wasmtime_export.definition;
-            match val {
+            match *val {
                 Val::I32(i) => *definition.as_i32_mut() = i,
                 Val::I64(i) => *definition.as_i64_mut() = i,
                 Val::F32(f) => *definition.as_u32_mut() = f,
                 Val::F64(f) => *definition.as_u64_mut() = f,
+                Val::V128(v) => *definition.as_u128_mut() = v,
+                Val::AnyRef(_) | Val::FuncRef(_) => {
+                    let bits = self.store.global_refs().root(val);
+                    write_ref_bits(definition, bits);
+                }
+                Val::String(_) => *self.host_value().borrow_mut() = val.clone(),
                 _ => unimplemented!("Global::set for {:?}", val.ty()),
             }
         }
-        Ok(())
+    }
+
+    fn host_value(&self) -> &RefCell<Val> {
+        self.host_value
+            .as_ref()
+            .expect("string globals always have a host value slot")
+    }
+
+    /// Returns a [`TypedGlobal`] view of this global whose content type is
+    /// statically known to be `T`.
+    ///
+    /// The content type is checked once here, so reads and writes through the
+    /// returned view don't need to compare types on each access.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the content type of this global isn't `T`.
+    pub fn typed<T: WasmTy>(&self) -> Result<TypedGlobal<T>> {
+        let mut tys = iter::once(self.ty().content().clone());
+        T::matches(&mut tys)?;
+        if tys.next().is_some() {
+            bail!(
+                "Type mismatch, expected no value, got {:?}",
+                self.ty().content()
+            );
+        }
+        Ok(TypedGlobal {
+            global: self.clone(),
+            _marker: marker::PhantomData,
+        })
+    }
+
+    /// Returns a [`ReadOnlyGlobal`] view of this global, which can be read but
+    /// not written.
+    pub fn read_only(&self) -> ReadOnlyGlobal {
+        ReadOnlyGlobal {
+            global: self.clone(),
+        }
+    }
+
+    /// Returns the number of references currently keeping the instance which
+    /// backs this global alive.
+    ///
+    /// This is only intended for debugging instance leaks, for example to
+    /// confirm that dropping clones of this global releases the instance. It is
+    /// only available when debug assertions are enabled.
+    #[cfg(debug_assertions)]
+    pub fn debug_instance_refcount(&self) -> usize {
+        self.wasmtime_handle.refcount()
     }
 
     pub(crate) fn from_wasmtime_global(
@@ -278,8 +652,112 @@ impl Global {
             ty: ty,
             wasmtime_export,
             wasmtime_handle,
+            host_value: None,
         }
     }
+
+    /// Replaces the content type of this global with the one declared by the
+    /// module exporting it, since wasm globals themselves don't record whether
+    /// a reference is a `funcref` or an `anyref`.
+    pub(crate) fn with_declared_ty(mut self, ty: &GlobalType) -> Global {
+        self.ty = ty.clone();
+        self
+    }
+}
+
+/// Returns whether `val` may be stored in a global of `store`.
+///
+/// Host `anyref`s aren't tied to any store, the global roots them itself.
+fn global_val_from_store(val: &Val, store: &Store) -> bool {
+    match val {
+        Val::AnyRef(_) => true,
+        _ => val.comes_from_same_store(store),
+    }
+}
+
+/// Reads the pointer-sized value which identifies the reference stored in a
+/// reference-typed global, see `GlobalRefs`.
+pub(crate) unsafe fn read_ref_bits(definition: &VMGlobalDefinition) -> usize {
+    ptr::read(definition.as_u128() as *const u128 as *const usize)
+}
+
+/// Writes the pointer-sized value which identifies a reference into a
+/// reference-typed global.
+unsafe fn write_ref_bits(definition: &mut VMGlobalDefinition, bits: usize) {
+    ptr::write(definition.as_u128_mut() as *mut u128 as *mut usize, bits)
+}
+
+/// A view of a [`Global`] whose content type has been checked to be `T`.
+///
+/// Created with [`Global::typed`], this reads and writes the global's value
+/// directly as a `T` without going through [`Val`].
+#[derive(Clone)]
+pub struct TypedGlobal<T> {
+    global: Global,
+    _marker: marker::PhantomData<fn() -> T>,
+}
+
+impl<T: WasmTy> TypedGlobal<T> {
+    /// Returns the underlying [`Global`].
+    pub fn global(&self) -> &Global {
+        &self.global
+    }
+
+    /// Returns the current value of this global.
+    pub fn get(&self) -> T {
+        unsafe {
+            let definition = &*self.global.wasmtime_export.definition;
+            let mut ptr = definition.as_u128() as *const u128;
+            T::from_abi(ptr::null_mut(), T::load(&mut ptr))
+        }
+    }
+
+    /// Sets the current value of this global to `val`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if this global is not mutable.
+    pub fn set(&self, val: T) -> Result<()> {
+        if self.global.ty().mutability() != Mutability::Var {
+            bail!("immutable global cannot be set");
+        }
+        unsafe {
+            let definition = &mut *self.global.wasmtime_export.definition;
+            T::store(val.into_abi(), definition.as_u128_mut());
+        }
+        Ok(())
+    }
+}
+
+/// A view of a [`Global`] which only allows reading its value.
+///
+/// Created with [`Global::read_only`], this can be handed to code which should
+/// observe a global without being able to modify it, regardless of whether the
+/// global itself is mutable. There is no way to get the underlying [`Global`]
+/// back out of this view, so the following doesn't compile:
+///
+/// ```compile_fail
+/// # use wasmtime::*;
+/// # let store = Store::default();
+/// # let ty = GlobalType::new(ValType::I32, Mutability::Var);
+/// let global = Global::new(&store, ty, Val::I32(1)).unwrap();
+/// global.read_only().set(Val::I32(2));
+/// ```
+#[derive(Clone)]
+pub struct ReadOnlyGlobal {
+    global: Global,
+}
+
+impl ReadOnlyGlobal {
+    /// Returns the underlying type of this global.
+    pub fn ty(&self) -> &GlobalType {
+        self.global.ty()
+    }
+
+    /// Returns the current [`Val`] of this global.
+    pub fn get(&self) -> Val {
+        self.global.get()
+    }
 }
 
 /// A WebAssembly `table`, or an array of values.
@@ -303,6 +781,112 @@ pub struct Table {
     ty: TableType,
     wasmtime_handle: InstanceHandle,
     wasmtime_export: wasmtime_runtime::ExportTable,
+    // The elements of tables of plain values, such as `i32`, which can't be
+    // stored in the wasm table definition itself. The wasm table is still
+    // grown alongside this so that it enforces the table's limits.
+    host_values: Option<Rc<RefCell<Vec<Val>>>>,
+}
+
+fn into_table_item(
+    ty: &TableType,
+    val: Val,
+    store: &Store,
+) -> Result<wasmtime_runtime::VMCallerCheckedAnyfunc> {
+    let element = ty.element();
+    let matches = match (val.ty(), element) {
+        _ if val.is_null() => element.is_ref(),
+        // funcrefs are a subtype of anyref
+        (ValType::FuncRef, ValType::AnyRef) => true,
+        (ty, element) => ty == *element,
+    };
+    if !matches {
+        bail!("table holds {} but got {}", element, val.ty());
+    }
+    // Non-null `anyref`s can't be represented by the element itself, so the
+    // element is left null and the reference is kept as its root instead, see
+    // `table_item_root`.
+    if let Val::AnyRef(_) = val {
+        return into_checked_anyfunc(Val::null(), store);
+    }
+    into_checked_anyfunc(val, store)
+}
+
+/// Returns the host object that must be kept alive while `val` is stored in a
+/// table owned by `handle`.
+///
+/// A `Func` keeps the instance backing it alive, so rooting it in the table
+/// ensures a function stored in a table outlives the handle it was stored
+/// through. Functions of the owning instance itself are not rooted since that
+/// would create a reference cycle; cycles between distinct instances are
+/// not collected.
+///
+/// Non-null `anyref`s are stored entirely as the root, which keeps the host
+/// object alive until the element is overwritten or the table is dropped.
+fn table_item_root(handle: &InstanceHandle, val: &Val) -> Option<Rc<dyn Any>> {
+    match val {
+        Val::FuncRef(Some(f)) if f.wasmtime_function().vmctx != handle.vmctx_ptr() => {
+            Some(Rc::new(f.clone()))
+        }
+        Val::AnyRef(r) if !val.is_null() => Some(Rc::new(r.clone())),
+        _ => None,
+    }
+}
+
+/// Reads the element at `item_index` of a table of references of type
+/// `element` owned by `handle`, returning `None` if it's out of bounds.
+fn get_table_item(
+    handle: &InstanceHandle,
+    table_index: wasm::DefinedTableIndex,
+    item_index: u32,
+    element: &ValType,
+    store: &Store,
+) -> Option<Val> {
+    let item = handle.table_get(table_index, item_index)?;
+    if let Some(root) = handle.table_root(table_index, item_index) {
+        if let Some(r) = (*root).downcast_ref::<AnyRef>() {
+            return Some(Val::AnyRef(r.clone()));
+        }
+    }
+    match from_checked_anyfunc(item, store) {
+        val if val.is_null() => Some(Val::default_for(element)),
+        val => Some(val),
+    }
+}
+
+/// Returns the indices of the `delta` elements most recently added to a table
+/// or memory which now has `len` elements.
+///
+/// Returns an error rather than wrapping around if `len` is inconsistent with
+/// `delta`.
+fn grown_range(len: u32, delta: u32) -> Result<Range<u32>> {
+    match len.checked_sub(delta) {
+        Some(start) => Ok(start..len),
+        None => bail!("size {} is too small to have grown by {}", len, delta),
+    }
+}
+
+/// Stores `val` at `index` of the elements `values` of a table of plain values
+/// of type `ty`, returning the value previously stored there.
+fn replace_table_value(
+    ty: &TableType,
+    values: &RefCell<Vec<Val>>,
+    index: u32,
+    val: Val,
+) -> Result<Val> {
+    check_table_value(ty, &val)?;
+    let mut values = values.borrow_mut();
+    let slot = values
+        .get_mut(index as usize)
+        .ok_or_else(|| anyhow!("table element index out of bounds"))?;
+    Ok(mem::replace(slot, val))
+}
+
+/// Checks that `val` can be stored in a table of plain values of type `ty`.
+fn check_table_value(ty: &TableType, val: &Val) -> Result<()> {
+    if val.ty() != *ty.element() {
+        bail!("table holds {} but got {}", ty.element(), val.ty());
+    }
+    Ok(())
 }
 
 fn set_table_item(
@@ -310,9 +894,10 @@ fn set_table_item(
     table_index: wasm::DefinedTableIndex,
     item_index: u32,
     item: wasmtime_runtime::VMCallerCheckedAnyfunc,
+    root: Option<Rc<dyn Any>>,
 ) -> Result<()> {
     handle
-        .table_set(table_index, item_index, item)
+        .table_set_rooted(table_index, item_index, item, root)
         .map_err(|()| anyhow!("table element index out of bounds"))
 }
 
@@ -329,14 +914,31 @@ impl Table {
     ///
     /// Returns an error if `init` does not match the element type of the table.
     pub fn new(store: &Store, ty: TableType, init: Val) -> Result<Table> {
-        let item = into_checked_anyfunc(init, store)?;
         let (wasmtime_handle, wasmtime_export) = generate_table_export(store, &ty)?;
+        if !ty.element().is_ref() {
+            check_table_value(&ty, &init)?;
+            let len = unsafe { (*wasmtime_export.definition).current_elements };
+            let values = vec![init; len as usize];
+            return Ok(Table {
+                store: store.clone(),
+                ty,
+                wasmtime_handle,
+                wasmtime_export,
+                host_values: Some(Rc::new(RefCell::new(values))),
+            });
+        }
+        let root = table_item_root(&wasmtime_handle, &init);
+        let item = into_table_item(&ty, init, store)?;
 
-        // Initialize entries with the init value.
-        let definition = unsafe { &*wasmtime_export.definition };
-        let index = wasmtime_handle.table_index(definition);
-        for i in 0..definition.current_elements {
-            set_table_item(&wasmtime_handle, index, i, item.clone())?;
+        // Initialize entries with the init value. Freshly created tables are
+        // already filled with null entries, so there's nothing to do if that's
+        // what we've been asked to initialize with.
+        if !item.func_ptr.is_null() || root.is_some() {
+            let definition = unsafe { &*wasmtime_export.definition };
+            let index = wasmtime_handle.table_index(definition);
+            for i in 0..definition.current_elements {
+                set_table_item(&wasmtime_handle, index, i, item.clone(), root.clone())?;
+            }
         }
 
         Ok(Table {
@@ -344,6 +946,7 @@ impl Table {
             ty,
             wasmtime_handle,
             wasmtime_export,
+            host_values: None,
         })
     }
 
@@ -364,9 +967,37 @@ impl Table {
     ///
     /// Returns `None` if `index` is out of bounds.
     pub fn get(&self, index: u32) -> Option<Val> {
+        if let Some(values) = &self.host_values {
+            return values.borrow().get(index as usize).cloned();
+        }
         let table_index = self.wasmtime_table_index();
-        let item = self.wasmtime_handle.table_get(table_index, index)?;
-        Some(from_checked_anyfunc(item, &self.store))
+        get_table_item(
+            &self.wasmtime_handle,
+            table_index,
+            index,
+            self.ty.element(),
+            &self.store,
+        )
+    }
+
+    /// Returns the table element value at `index`.
+    ///
+    /// This is like [`Table::get`], for callers which have already checked
+    /// that `index` is in bounds.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds, whereas [`Table::get`] returns
+    /// `None`.
+    pub fn at(&self, index: u32) -> Val {
+        match self.get(index) {
+            Some(val) => val,
+            None => panic!(
+                "table index {} out of bounds for table of size {}",
+                index,
+                self.size()
+            ),
+        }
     }
 
     /// Writes the `val` provided into `index` within this table.
@@ -376,9 +1007,53 @@ impl Table {
     /// Returns an error if `index` is out of bounds or if `val` does not have
     /// the right type to be stored in this table.
     pub fn set(&self, index: u32, val: Val) -> Result<()> {
+        if let Some(values) = &self.host_values {
+            return replace_table_value(&self.ty, values, index, val).map(drop);
+        }
+        let table_index = self.wasmtime_table_index();
+        let root = table_item_root(&self.wasmtime_handle, &val);
+        let item = into_table_item(&self.ty, val, &self.store)?;
+        set_table_item(&self.wasmtime_handle, table_index, index, item, root)
+    }
+
+    /// Writes the `val` provided into `index` within this table, returning the
+    /// value that was previously stored there.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `index` is out of bounds or if `val` does not have
+    /// the right type to be stored in this table.
+    pub fn replace(&self, index: u32, val: Val) -> Result<Val> {
+        if let Some(values) = &self.host_values {
+            return replace_table_value(&self.ty, values, index, val);
+        }
         let table_index = self.wasmtime_table_index();
-        let item = into_checked_anyfunc(val, &self.store)?;
-        set_table_item(&self.wasmtime_handle, table_index, index, item)
+        let root = table_item_root(&self.wasmtime_handle, &val);
+        let item = into_table_item(&self.ty, val, &self.store)?;
+        let prev = get_table_item(
+            &self.wasmtime_handle,
+            table_index,
+            index,
+            self.ty.element(),
+            &self.store,
+        )
+        .ok_or_else(|| anyhow!("table element index out of bounds"))?;
+        set_table_item(&self.wasmtime_handle, table_index, index, item, root)?;
+        Ok(prev)
+    }
+
+    /// Resets every element of this table to the null reference.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if this table does not hold references.
+    pub fn clear(&self) -> Result<()> {
+        let table_index = self.wasmtime_table_index();
+        let item = into_table_item(&self.ty, Val::null(), &self.store)?;
+        for i in 0..self.size() {
+            set_table_item(&self.wasmtime_handle, table_index, i, item.clone(), None)?;
+        }
+        Ok(())
     }
 
     /// Returns the current size of this table.
@@ -386,6 +1061,11 @@ impl Table {
         unsafe { (&*self.wasmtime_export.definition).current_elements }
     }
 
+    /// Returns `true` if this table has no elements.
+    pub fn is_empty(&self) -> bool {
+        self.size() == 0
+    }
+
     /// Grows the size of this table by `delta` more elements, initialization
     /// all new elements to `init`.
     ///
@@ -396,12 +1076,22 @@ impl Table {
     /// error if `init` is not of the right type.
     pub fn grow(&self, delta: u32, init: Val) -> Result<u32> {
         let index = self.wasmtime_table_index();
-        let item = into_checked_anyfunc(init, &self.store)?;
+        if let Some(values) = &self.host_values {
+            check_table_value(&self.ty, &init)?;
+            return match self.wasmtime_handle.clone().table_grow(index, delta) {
+                Some(len) => {
+                    values.borrow_mut().resize(len as usize, init);
+                    Ok(len)
+                }
+                None => bail!("failed to grow table by `{}`", delta),
+            };
+        }
+        let root = table_item_root(&self.wasmtime_handle, &init);
+        let item = into_table_item(&self.ty, init, &self.store)?;
         if let Some(len) = self.wasmtime_handle.clone().table_grow(index, delta) {
             let mut wasmtime_handle = self.wasmtime_handle.clone();
-            for i in 0..delta {
-                let i = len - (delta - i);
-                set_table_item(&mut wasmtime_handle, index, i, item.clone())?;
+            for i in grown_range(len, delta)? {
+                set_table_item(&mut wasmtime_handle, index, i, item.clone(), root.clone())?;
             }
             Ok(len)
         } else {
@@ -409,13 +1099,71 @@ impl Table {
         }
     }
 
+    /// Grows this table so that it holds at least `target` elements,
+    /// initializing all new elements to `init`, and returns the resulting size.
+    ///
+    /// This is a no-op if the table already holds `target` or more elements.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the table needs to grow but cannot, for example if
+    /// `target` exceeds the table's maximum size, or if `init` is not of the
+    /// right type.
+    pub fn ensure_size(&self, target: u32, init: Val) -> Result<u32> {
+        let size = self.size();
+        if target > size {
+            self.grow(target - size, init)?;
+        }
+        Ok(self.size())
+    }
+
+    /// Writes `val` into the `len` elements of this table starting at
+    /// `index`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the range is out of bounds of this table or if
+    /// `val` does not have the right type to be stored in this table. In
+    /// either case the table is left unmodified.
+    pub fn fill(&self, index: u32, val: Val, len: u32) -> Result<()> {
+        let end = match index.checked_add(len) {
+            Some(end) if end <= self.size() => end,
+            _ => bail!(
+                "fill of {} elements at {} out of bounds of table of size {}",
+                len,
+                index,
+                self.size()
+            ),
+        };
+        if let Some(values) = &self.host_values {
+            check_table_value(&self.ty, &val)?;
+            for slot in &mut values.borrow_mut()[index as usize..end as usize] {
+                *slot = val.clone();
+            }
+            return Ok(());
+        }
+        let table_index = self.wasmtime_table_index();
+        let root = table_item_root(&self.wasmtime_handle, &val);
+        let item = into_table_item(&self.ty, val, &self.store)?;
+        for i in index..end {
+            set_table_item(
+                &self.wasmtime_handle,
+                table_index,
+                i,
+                item.clone(),
+                root.clone(),
+            )?;
+        }
+        Ok(())
+    }
+
     /// Copy `len` elements from `src_table[src_index..]` into
     /// `dst_table[dst_index..]`.
     ///
     /// # Errors
     ///
     /// Returns an error if the range is out of bounds of either the source or
-    /// destination tables.
+    /// destination tables, or if the tables have different element types.
     pub fn copy(
         dst_table: &Table,
         dst_index: u32,
@@ -426,6 +1174,125 @@ impl Table {
         if !Store::same(&dst_table.store, &src_table.store) {
             bail!("cross-`Store` table copies are not supported");
         }
+        Table::copy_raw(
+            "Table::copy",
+            dst_table,
+            dst_index,
+            src_table,
+            src_index,
+            len,
+        )
+    }
+
+    /// Copy `len` elements from `src_table[src_index..]` into
+    /// `dst_table[dst_index..]`, permitting the tables to belong to different
+    /// [`Store`]s.
+    ///
+    /// References are bound to the store they were created in, so a copy
+    /// between stores is only allowed if the elements being copied don't carry
+    /// any store affinity: either the tables hold plain values, or every
+    /// element in the source range is null.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the range is out of bounds of either the source or
+    /// destination tables, if the tables have different element types, or if
+    /// the tables are in different stores and the source range contains a
+    /// non-null reference.
+    pub fn copy_cross_store(
+        dst_table: &Table,
+        dst_index: u32,
+        src_table: &Table,
+        src_index: u32,
+        len: u32,
+    ) -> Result<()> {
+        if !Store::same(&dst_table.store, &src_table.store) && src_table.ty().element().is_ref() {
+            let table_index = src_table.wasmtime_table_index();
+            for i in 0..len {
+                let item = src_index.checked_add(i).and_then(|i| {
+                    get_table_item(
+                        &src_table.wasmtime_handle,
+                        table_index,
+                        i,
+                        src_table.ty.element(),
+                        &src_table.store,
+                    )
+                });
+                match item {
+                    Some(item) if !item.is_null() => {
+                        bail!("cross-`Store` copies of non-null references are not supported")
+                    }
+                    Some(_) => {}
+                    // Out of bounds, which the copy itself will report.
+                    None => break,
+                }
+            }
+        }
+        Table::copy_raw(
+            "Table::copy_cross_store",
+            dst_table,
+            dst_index,
+            src_table,
+            src_index,
+            len,
+        )
+    }
+
+    /// Shared implementation of the table copy operations, where `op` names
+    /// the operation in error messages.
+    ///
+    /// Copies initiated from the host have no wasm instruction to point at, so
+    /// bounds are checked here to produce an error naming `op` rather than a
+    /// trap with a meaningless source location.
+    fn copy_raw(
+        op: &str,
+        dst_table: &Table,
+        dst_index: u32,
+        src_table: &Table,
+        src_index: u32,
+        len: u32,
+    ) -> Result<()> {
+        let (dst_element, src_element) = (dst_table.ty().element(), src_table.ty().element());
+        if dst_element != src_element {
+            bail!(
+                "cannot copy {} elements into a table of {}",
+                src_element,
+                dst_element
+            );
+        }
+
+        let in_bounds = |table: &Table, index: u32| {
+            index
+                .checked_add(len)
+                .map_or(false, |end| end <= table.size())
+        };
+        if !in_bounds(src_table, src_index) {
+            bail!(
+                "{} src+len out of bounds: {} + {} exceeds table size {}",
+                op,
+                src_index,
+                len,
+                src_table.size()
+            );
+        }
+        if !in_bounds(dst_table, dst_index) {
+            bail!(
+                "{} dst+len out of bounds: {} + {} exceeds table size {}",
+                op,
+                dst_index,
+                len,
+                dst_table.size()
+            );
+        }
+
+        // Tables of plain values keep their elements on the host. The element
+        // types match, so either both tables do or neither does.
+        if let (Some(dst), Some(src)) = (&dst_table.host_values, &src_table.host_values) {
+            let len = len as usize;
+            let items = src.borrow()[src_index as usize..][..len].to_vec();
+            dst.borrow_mut()[dst_index as usize..][..len].clone_from_slice(&items);
+            return Ok(());
+        }
 
         // NB: We must use the `dst_table`'s `wasmtime_handle` for the
         // `dst_table_index` and vice versa for `src_table` since each table can
@@ -448,6 +1315,17 @@ impl Table {
         Ok(())
     }
 
+    /// Returns the number of references currently keeping the instance which
+    /// backs this table alive.
+    ///
+    /// This is only intended for debugging instance leaks, for example to
+    /// confirm that dropping clones of this table releases the instance. It is
+    /// only available when debug assertions are enabled.
+    #[cfg(debug_assertions)]
+    pub fn debug_instance_refcount(&self) -> usize {
+        self.wasmtime_handle.refcount()
+    }
+
     pub(crate) fn from_wasmtime_table(
         wasmtime_export: wasmtime_runtime::ExportTable,
         store: &Store,
@@ -459,6 +1337,7 @@ impl Table {
             ty,
             wasmtime_handle,
             wasmtime_export,
+            host_values: None,
         }
     }
 }
@@ -478,6 +1357,11 @@ impl Table {
 /// cloning process only performs a shallow clone, so two cloned `Memory`
 /// instances are equivalent in their functionality.
 ///
+/// The same holds for a `Memory` created on the host and passed as an import
+/// to an [`Instance`](crate::Instance): the host and wasm share one linear
+/// memory, so writes and growth from either side are immediately visible to
+/// the other.
+///
 /// # `Memory` and threads
 ///
 /// It is intended that `Memory` is safe to share between threads. At this time
@@ -489,23 +1373,94 @@ pub struct Memory {
     ty: MemoryType,
     wasmtime_handle: InstanceHandle,
     wasmtime_export: wasmtime_runtime::ExportMemory,
+    grow_hooks: Rc<RefCell<Vec<Rc<dyn Fn(u32, u32)>>>>,
+}
+
+/// The outcome of a successful [`Memory::grow`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GrowResult {
+    /// The number of pages the memory had before it was grown.
+    pub prev_pages: u32,
+    /// Whether growing moved the memory to a new base address, invalidating
+    /// [`Memory::data_ptr`] and any slices or pointers derived from it.
+    ///
+    /// Memories which reserved enough address space up front for their
+    /// maximum size are always grown in place.
+    pub relocated: bool,
+}
+
+macro_rules! scalar_accessors {
+    ($(($ty:ident $load:ident $store:ident))*) => ($(
+        /// Reads a little-endian value starting at byte `offset` of this
+        /// memory.
+        ///
+        /// # Errors
+        ///
+        /// Returns an error if the value lies out of bounds of this memory.
+        pub fn $load(&self, offset: usize) -> Result<$ty> {
+            let mut bytes = [0; mem::size_of::<$ty>()];
+            self.load_blob(offset, &mut bytes)?;
+            Ok($ty::from_le_bytes(bytes))
+        }
+
+        /// Writes `v` in little-endian byte order at byte `offset` of this
+        /// memory.
+        ///
+        /// # Errors
+        ///
+        /// Returns an error if the value lies out of bounds of this memory, in
+        /// which case nothing is written.
+        pub fn $store(&self, offset: usize, v: $ty) -> Result<()> {
+            self.store_blob(offset, &v.to_le_bytes(), false)
+        }
+    )*)
 }
 
 impl Memory {
+    /// The size, in bytes, of a WebAssembly page.
+    ///
+    /// Memory sizes and limits are measured in pages, so this is the factor to
+    /// convert them to and from byte lengths.
+    pub const PAGE_SIZE: usize = wasmtime_environ::WASM_PAGE_SIZE as usize;
+
     /// Creates a new WebAssembly memory given the configuration of `ty`.
     ///
     /// The `store` argument is a general location for cache information, and
     /// otherwise the memory will immediately be allocated according to the
     /// type's configuration. All WebAssembly memory is initialized to zero.
-    pub fn new(store: &Store, ty: MemoryType) -> Memory {
-        let (wasmtime_handle, wasmtime_export) =
-            generate_memory_export(store, &ty).expect("generated memory");
-        Memory {
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the memory could not be allocated, for example
+    /// because its initial size would exceed the limit set with
+    /// [`Store::set_memory_limit`].
+    pub fn new(store: &Store, ty: MemoryType) -> Result<Memory> {
+        let (wasmtime_handle, wasmtime_export) = generate_memory_export(store, &ty)?;
+        Ok(Memory {
             store: store.clone(),
             ty,
             wasmtime_handle,
             wasmtime_export,
-        }
+            grow_hooks: Default::default(),
+        })
+    }
+
+    /// Creates a new WebAssembly memory given the configuration of `ty`, with
+    /// its contents initialized from `data`.
+    ///
+    /// The memory is allocated as with [`Memory::new`] and then grown, if
+    /// necessary, so that all of `data` fits before it is copied in at offset
+    /// zero. Any remaining bytes of the memory are zero.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `data` is larger than the maximum size of `ty`, or
+    /// if the memory could not be grown to fit `data`.
+    pub fn with_data(store: &Store, ty: MemoryType, data: &[u8]) -> Result<Memory> {
+        check_fits_in_max(&ty, data)?;
+        let memory = Memory::new(store, ty)?;
+        memory.store_blob(0, data, true)?;
+        Ok(memory)
     }
 
     /// Returns the underlying type of this memory.
@@ -575,9 +1530,50 @@ impl Memory {
         unsafe { (*self.wasmtime_export.definition).current_length }
     }
 
+    /// Returns the base pointer and byte length of this memory, read together
+    /// from a single snapshot of the memory's definition.
+    ///
+    /// This is equivalent to calling [`Memory::data_ptr`] and
+    /// [`Memory::data_size`], except that the two values are guaranteed to be
+    /// consistent with each other. All the caveats of
+    /// [`Memory::data_unchecked`] apply when turning them into a slice: the
+    /// values are invalidated as soon as wasm code runs or the memory grows.
+    pub fn raw_parts(&self) -> (*mut u8, usize) {
+        let definition = unsafe { *self.wasmtime_export.definition };
+        (definition.base, definition.current_length)
+    }
+
+    /// Returns the range of host addresses spanned by this memory, from its
+    /// base pointer up to one past its last byte.
+    ///
+    /// This is convenient when handing memory to C APIs which take a
+    /// `begin`/`end` pointer pair. Like [`Memory::raw_parts`], both ends come
+    /// from a single read of the memory's definition, so they are consistent
+    /// with each other.
+    ///
+    /// The caveats of [`Memory::data_unchecked`] apply to the returned
+    /// pointers: calling into wasm, or growing the memory by any means, may
+    /// relocate it and leave the range dangling, and foreign code writing
+    /// through the range must not be reentered by wasm using this memory.
+    pub fn ptr_range(&self) -> Range<*mut u8> {
+        let (base, len) = self.raw_parts();
+        base..base.wrapping_add(len)
+    }
+
     /// Returns the size, in pages, of this wasm memory.
     pub fn size(&self) -> u32 {
-        (self.data_size() / wasmtime_environ::WASM_PAGE_SIZE as usize) as u32
+        (self.data_size() / Memory::PAGE_SIZE) as u32
+    }
+
+    /// Returns the number of pages this memory can still grow by before it
+    /// reaches its maximum size, or `None` if the memory has no maximum.
+    ///
+    /// This returns `Some(0)` if the memory is already at its maximum size.
+    /// Note that [`Memory::grow`] may still fail for smaller deltas, for example
+    /// if the host is out of memory.
+    pub fn growable_pages(&self) -> Option<u32> {
+        let max = self.ty().limits().max()?;
+        Some(max.saturating_sub(self.size()))
     }
 
     /// Grows this WebAssembly memory by `delta` pages.
@@ -587,21 +1583,381 @@ impl Memory {
     /// cause [`Memory::data_ptr`] to return a new value. Additionally previous
     /// slices into this memory may no longer be valid.
     ///
-    /// On success returns the number of pages this memory previously had
-    /// before the growth succeeded.
+    /// On success returns a [`GrowResult`] with the number of pages this
+    /// memory previously had and whether it was relocated, in which case any
+    /// pointers into it that were cached before the call must be refreshed.
     ///
     /// # Errors
     ///
     /// Returns an error if memory could not be grown, for example if it exceeds
-    /// the maximum limits of this memory.
-    pub fn grow(&self, delta: u32) -> Result<u32> {
+    /// the maximum limits of this memory or the store's memory limit set with
+    /// [`Store::set_memory_limit`].
+    pub fn grow(&self, delta: u32) -> Result<GrowResult> {
+        let base = self.data_ptr();
+        let bytes = (delta as usize).saturating_mul(Memory::PAGE_SIZE);
+        self.store.check_memory_limit(bytes)?;
+
         let index = self
             .wasmtime_handle
             .memory_index(unsafe { &*self.wasmtime_export.definition });
-        self.wasmtime_handle
+        let prev = self
+            .wasmtime_handle
             .clone()
             .memory_grow(index, delta)
-            .ok_or_else(|| anyhow!("failed to grow memory"))
+            .ok_or_else(|| anyhow!("failed to grow memory"))?;
+
+        let new = prev
+            .checked_add(delta)
+            .ok_or_else(|| anyhow!("memory of {} pages cannot grow by {}", prev, delta))?;
+
+        // Clone the list of hooks so that a hook may itself register more.
+        let hooks = self.grow_hooks.borrow().clone();
+        for hook in hooks {
+            hook(prev, new);
+        }
+        Ok(GrowResult {
+            prev_pages: prev,
+            relocated: self.data_ptr() != base,
+        })
+    }
+
+    /// Grows this WebAssembly memory by `delta` pages, returning the number of
+    /// pages it previously had.
+    ///
+    /// This is what [`Memory::grow`] used to return.
+    #[deprecated(note = "use `Memory::grow` and `GrowResult::prev_pages` instead")]
+    pub fn grow_pages(&self, delta: u32) -> Result<u32> {
+        Ok(self.grow(delta)?.prev_pages)
+    }
+
+    /// Registers a callback which is invoked with the old and new size, in
+    /// pages, every time [`Memory::grow`] succeeds.
+    ///
+    /// Callbacks are shared between clones of this `Memory`, and are invoked
+    /// in the order they were registered. Growth performed by wasm's
+    /// `memory.grow` instruction, or through a `Memory` handle obtained
+    /// separately, for example from another call to
+    /// [`Instance::get_export`](crate::Instance::get_export), is not reported.
+    pub fn on_grow(&self, cb: impl Fn(u32, u32) + 'static) {
+        self.grow_hooks.borrow_mut().push(Rc::new(cb));
+    }
+
+    /// Grows this WebAssembly memory so that it's at least `target_pages`
+    /// pages large.
+    ///
+    /// This is a no-op if the memory already has `target_pages` or more pages.
+    /// Otherwise it behaves like [`Memory::grow`] with the difference between
+    /// `target_pages` and [`Memory::size`].
+    ///
+    /// Returns the number of pages this memory had before this call.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `target_pages` exceeds the maximum size of this
+    /// memory, or if the memory could not otherwise be grown.
+    pub fn grow_to(&self, target_pages: u32) -> Result<u32> {
+        let size = self.size();
+        if target_pages <= size {
+            return Ok(size);
+        }
+        if let Some(max) = self.ty().limits().max() {
+            if target_pages > max {
+                bail!(
+                    "cannot grow memory to {} pages, its maximum is {} pages",
+                    target_pages,
+                    max
+                );
+            }
+        }
+        Ok(self.grow(target_pages - size)?.prev_pages)
+    }
+
+    /// Copies `data` into this memory starting at byte `offset`.
+    ///
+    /// If `grow_if_needed` is `true` and `offset + data.len()` lies beyond the
+    /// current end of this memory, the memory is first grown by as many pages
+    /// as are needed to fit the whole of `data`. Growing may relocate the
+    /// memory, see [`Memory::grow`] for more information.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `data` doesn't fit in this memory and either
+    /// `grow_if_needed` is `false` or the memory could not be grown. No bytes
+    /// are written in that case.
+    pub fn store_blob(&self, offset: usize, data: &[u8], grow_if_needed: bool) -> Result<()> {
+        let end = match offset.checked_add(data.len()) {
+            Some(end) => end,
+            None => bail!(
+                "blob of {} bytes at offset {} overflows",
+                data.len(),
+                offset
+            ),
+        };
+        if end > self.data_size() {
+            if !grow_if_needed {
+                bail!(
+                    "blob of {} bytes at offset {} is out of bounds of memory",
+                    data.len(),
+                    offset
+                );
+            }
+            let page_size = Memory::PAGE_SIZE;
+            let pages = (end - self.data_size() + page_size - 1) / page_size;
+            if pages > u32::max_value() as usize {
+                bail!("failed to grow memory by {} pages", pages);
+            }
+            self.grow(pages as u32)?;
+        }
+        unsafe {
+            self.data_unchecked_mut()[offset..end].copy_from_slice(data);
+        }
+        Ok(())
+    }
+
+    /// Writes `vals` into this memory one after the other, starting at byte
+    /// `offset`, and returns the number of bytes written.
+    ///
+    /// Values are laid out like the fields of a C struct: each is stored
+    /// little-endian in [`ValType::size_bytes`] bytes, aligned to its size
+    /// relative to `offset`, with any padding between values zeroed. `offset`
+    /// itself should therefore be aligned to the largest of those sizes.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if any value doesn't have a fixed size in memory, such
+    /// as a string or a reference, or if the values don't fit in this memory.
+    /// No bytes are written in that case.
+    pub fn write_vals(&self, offset: usize, vals: &[Val]) -> Result<usize> {
+        let mut bytes = Vec::new();
+        for val in vals {
+            let size = match val.ty().size_bytes() {
+                Some(size) => size,
+                None => bail!("cannot write a value of type {} to memory", val.ty()),
+            };
+            let aligned = (bytes.len() + size - 1) / size * size;
+            bytes.resize(aligned, 0);
+            match *val {
+                Val::I32(i) | Val::S32(i) => bytes.extend_from_slice(&i.to_le_bytes()),
+                Val::I64(i) | Val::S64(i) => bytes.extend_from_slice(&i.to_le_bytes()),
+                Val::F32(bits) | Val::U32(bits) => bytes.extend_from_slice(&bits.to_le_bytes()),
+                Val::F64(bits) | Val::U64(bits) => bytes.extend_from_slice(&bits.to_le_bytes()),
+                Val::V128(v) => bytes.extend_from_slice(&v.to_le_bytes()),
+                Val::S8(i) => bytes.extend_from_slice(&i.to_le_bytes()),
+                Val::U8(i) => bytes.extend_from_slice(&i.to_le_bytes()),
+                Val::S16(i) => bytes.extend_from_slice(&i.to_le_bytes()),
+                Val::U16(i) => bytes.extend_from_slice(&i.to_le_bytes()),
+                Val::AnyRef(_) | Val::FuncRef(_) | Val::String(_) => unreachable!(),
+            }
+        }
+        self.store_blob(offset, &bytes, false)?;
+        Ok(bytes.len())
+    }
+
+    scalar_accessors! {
+        (i32 load_i32 store_i32)
+        (i64 load_i64 store_i64)
+        (f32 load_f32 store_f32)
+        (f64 load_f64 store_f64)
+    }
+
+    /// Copies `buf.len()` bytes starting at byte `offset` of this memory into
+    /// `buf`.
+    fn load_blob(&self, offset: usize, buf: &mut [u8]) -> Result<()> {
+        let data = unsafe { self.data_unchecked() };
+        match offset.checked_add(buf.len()) {
+            Some(end) if end <= data.len() => {
+                buf.copy_from_slice(&data[offset..end]);
+                Ok(())
+            }
+            _ => bail!(
+                "{} bytes at offset {} are out of bounds of memory",
+                buf.len(),
+                offset
+            ),
+        }
+    }
+
+    /// Reads values of the types `tys` from this memory, starting at byte
+    /// `offset`.
+    ///
+    /// This is the inverse of [`Memory::write_vals`], and expects values to
+    /// be laid out the same way.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if any type doesn't have a fixed size in memory, such
+    /// as a string or a reference, or if the values would extend beyond the
+    /// end of this memory.
+    pub fn read_vals(&self, offset: usize, tys: &[ValType]) -> Result<Vec<Val>> {
+        let data = unsafe { self.data_unchecked() };
+        let mut pos = 0;
+        let mut vals = Vec::with_capacity(tys.len());
+        for ty in tys {
+            let size = match ty.size_bytes() {
+                Some(size) => size,
+                None => bail!("cannot read a value of type {} from memory", ty),
+            };
+            pos = (pos + size - 1) / size * size;
+            let bytes = offset
+                .checked_add(pos)
+                .and_then(|start| data.get(start..start.checked_add(size)?))
+                .ok_or_else(|| {
+                    anyhow!(
+                        "{} at offset {} is out of bounds of memory",
+                        ty,
+                        offset.saturating_add(pos)
+                    )
+                })?;
+            pos += size;
+            let val = match ty {
+                ValType::I32 => Val::I32(i32::from_le_bytes(bytes.try_into().unwrap())),
+                ValType::I64 => Val::I64(i64::from_le_bytes(bytes.try_into().unwrap())),
+                ValType::F32 => Val::F32(u32::from_le_bytes(bytes.try_into().unwrap())),
+                ValType::F64 => Val::F64(u64::from_le_bytes(bytes.try_into().unwrap())),
+                ValType::V128 => Val::V128(u128::from_le_bytes(bytes.try_into().unwrap())),
+                ValType::S8 => Val::S8(i8::from_le_bytes(bytes.try_into().unwrap())),
+                ValType::S16 => Val::S16(i16::from_le_bytes(bytes.try_into().unwrap())),
+                ValType::S32 => Val::S32(i32::from_le_bytes(bytes.try_into().unwrap())),
+                ValType::S64 => Val::S64(i64::from_le_bytes(bytes.try_into().unwrap())),
+                ValType::U8 => Val::U8(u8::from_le_bytes(bytes.try_into().unwrap())),
+                ValType::U16 => Val::U16(u16::from_le_bytes(bytes.try_into().unwrap())),
+                ValType::U32 => Val::U32(u32::from_le_bytes(bytes.try_into().unwrap())),
+                ValType::U64 => Val::U64(u64::from_le_bytes(bytes.try_into().unwrap())),
+                ValType::AnyRef | ValType::FuncRef | ValType::String => unreachable!(),
+            };
+            vals.push(val);
+        }
+        Ok(vals)
+    }
+
+    /// Returns a copy of the current contents of this memory.
+    ///
+    /// The snapshot can later be written back with
+    /// [`Memory::restore_snapshot`].
+    pub fn take_snapshot(&self) -> Vec<u8> {
+        unsafe { self.data_unchecked().to_vec() }
+    }
+
+    /// Restores the contents of this memory from a `snapshot` previously
+    /// returned by [`Memory::take_snapshot`].
+    ///
+    /// The memory is grown if it's currently smaller than `snapshot`. If it's
+    /// larger, all bytes past the end of `snapshot` are reset to zero, since
+    /// memories can't shrink.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `snapshot` is larger than the maximum size of this
+    /// memory, or if the memory could not be grown to fit it. The contents of
+    /// the memory are left unmodified in that case.
+    pub fn restore_snapshot(&self, snapshot: &[u8]) -> Result<()> {
+        check_fits_in_max(self.ty(), snapshot)?;
+        self.store_blob(0, snapshot, true)?;
+        unsafe {
+            for byte in &mut self.data_unchecked_mut()[snapshot.len()..] {
+                *byte = 0;
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns `true` if this memory and `other` have the same size and hold
+    /// the same bytes.
+    pub fn contents_eq(&self, other: &Memory) -> bool {
+        if self.data_size() != other.data_size() {
+            return false;
+        }
+        unsafe { self.data_unchecked() == other.data_unchecked() }
+    }
+
+    /// Returns a [`MemoryGuard`] which gives read-only access to the contents
+    /// of this memory through [`Deref`](std::ops::Deref) and
+    /// [`AsRef<[u8]>`](AsRef), for use with byte-oriented APIs.
+    ///
+    /// # Safety
+    ///
+    /// All of the same safety caveats of [`Memory::data_unchecked`] apply for
+    /// as long as the returned guard is alive. In particular no wasm code may
+    /// run, and this memory must not be grown or written to, while the guard
+    /// exists.
+    pub unsafe fn guard(&self) -> MemoryGuard<'_> {
+        MemoryGuard { memory: self }
+    }
+
+    /// Calls `f` with a slice of the contents of this memory, returning its
+    /// result.
+    ///
+    /// Unlike [`Memory::data_unchecked`] the slice is only lent out for the
+    /// duration of `f`, which makes it harder to accidentally hold onto it
+    /// across calls into wasm.
+    ///
+    /// # Safety
+    ///
+    /// All of the same safety caveats of [`Memory::data_unchecked`] apply
+    /// while `f` is running. In particular `f` must not call into wasm code,
+    /// or grow or write to this memory.
+    pub unsafe fn with_slice<R>(&self, f: impl FnOnce(&[u8]) -> R) -> R {
+        f(self.data_unchecked())
+    }
+
+    /// Calls `f` with a mutable slice of the contents of this memory,
+    /// returning its result.
+    ///
+    /// This is the mutable counterpart of [`Memory::with_slice`].
+    ///
+    /// # Safety
+    ///
+    /// All of the same safety caveats of [`Memory::data_unchecked_mut`] apply
+    /// while `f` is running. In particular `f` must not call into wasm code,
+    /// grow this memory, or otherwise access it other than through the
+    /// provided slice.
+    pub unsafe fn with_slice_mut<R>(&self, f: impl FnOnce(&mut [u8]) -> R) -> R {
+        f(self.data_unchecked_mut())
+    }
+
+    /// Returns a [`MemoryReader`] which reads the contents of this memory,
+    /// starting at byte `offset`, through the [`std::io::Read`] trait.
+    ///
+    /// The reader will report end-of-file once it reaches the end of this
+    /// memory as given by [`Memory::data_size`] at the time of each read.
+    pub fn reader(&self, offset: usize) -> MemoryReader {
+        MemoryReader {
+            memory: self.clone(),
+            offset,
+        }
+    }
+
+    /// Returns a [`MemoryWriter`] which writes into this memory, starting at
+    /// byte `offset`, through the [`std::io::Write`] trait.
+    ///
+    /// Writes which run past the end of this memory, as given by
+    /// [`Memory::data_size`], are truncated and the writer then reports
+    /// [`std::io::ErrorKind::WriteZero`].
+    pub fn writer(&self, offset: usize) -> MemoryWriter {
+        MemoryWriter {
+            memory: self.clone(),
+            offset,
+        }
+    }
+
+    /// Returns a [`MemoryCursor`] which writes typed fields one after the
+    /// other into this memory, starting at byte `start`.
+    pub fn cursor(&self, start: usize) -> MemoryCursor {
+        MemoryCursor {
+            memory: self.clone(),
+            position: start,
+        }
+    }
+
+    /// Returns the number of references currently keeping the instance which
+    /// backs this memory alive.
+    ///
+    /// This is only intended for debugging instance leaks, for example to
+    /// confirm that dropping clones of this memory releases the instance. It is
+    /// only available when debug assertions are enabled.
+    #[cfg(debug_assertions)]
+    pub fn debug_instance_refcount(&self) -> usize {
+        self.wasmtime_handle.refcount()
     }
 
     pub(crate) fn from_wasmtime_memory(
@@ -615,6 +1971,212 @@ impl Memory {
             ty: ty,
             wasmtime_handle,
             wasmtime_export,
+            grow_hooks: Default::default(),
+        }
+    }
+}
+
+fn check_fits_in_max(ty: &MemoryType, data: &[u8]) -> Result<()> {
+    if let Some(max) = ty.limits().max() {
+        let page_size = Memory::PAGE_SIZE as u64;
+        if data.len() as u64 > u64::from(max) * page_size {
+            bail!(
+                "{} bytes of data exceed the maximum memory size of {} pages",
+                data.len(),
+                max
+            );
         }
     }
+    Ok(())
+}
+
+/// A read-only view of the contents of a [`Memory`].
+///
+/// Created with the unsafe [`Memory::guard`], this dereferences to the
+/// memory's bytes and implements `AsRef<[u8]>`. No wasm code may run, and the
+/// memory must not be grown or written to, while a guard is alive.
+pub struct MemoryGuard<'a> {
+    memory: &'a Memory,
+}
+
+impl Deref for MemoryGuard<'_> {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        unsafe { self.memory.data_unchecked() }
+    }
+}
+
+impl AsRef<[u8]> for MemoryGuard<'_> {
+    fn as_ref(&self) -> &[u8] {
+        self
+    }
+}
+
+/// A cursor over a [`Memory`] which implements [`std::io::Read`].
+///
+/// Created with [`Memory::reader`], this reads bytes sequentially out of linear
+/// memory, advancing its offset as it goes, and reports end-of-file once the
+/// end of the memory is reached.
+///
+/// Note that each read copies out of the memory's current contents, so the
+/// same caveats as [`Memory::data_unchecked`] apply about not running wasm code
+/// concurrently with a read.
+#[derive(Clone)]
+pub struct MemoryReader {
+    memory: Memory,
+    offset: usize,
+}
+
+impl MemoryReader {
+    /// Returns the byte offset in the memory that the next read will start at.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+}
+
+impl io::Read for MemoryReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let data = unsafe { self.memory.data_unchecked() };
+        let remaining = match data.get(self.offset..) {
+            Some(remaining) => remaining,
+            None => return Ok(0),
+        };
+        let amt = cmp::min(buf.len(), remaining.len());
+        buf[..amt].copy_from_slice(&remaining[..amt]);
+        self.offset += amt;
+        Ok(amt)
+    }
+}
+
+/// A cursor over a [`Memory`] which implements [`std::io::Write`].
+///
+/// Created with [`Memory::writer`], this writes bytes sequentially into linear
+/// memory, advancing its offset as it goes. Writing past the end of the memory
+/// fails rather than growing it.
+///
+/// The same caveats as [`Memory::data_unchecked_mut`] apply about not running
+/// wasm code concurrently with a write.
+#[derive(Clone)]
+pub struct MemoryWriter {
+    memory: Memory,
+    offset: usize,
+}
+
+impl MemoryWriter {
+    /// Returns the byte offset in the memory that the next write will start at.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+}
+
+impl io::Write for MemoryWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let data = unsafe { self.memory.data_unchecked_mut() };
+        let remaining = match data.get_mut(self.offset..) {
+            Some(remaining) if !remaining.is_empty() || buf.is_empty() => remaining,
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::WriteZero,
+                    "write past the end of linear memory",
+                ))
+            }
+        };
+        let amt = cmp::min(buf.len(), remaining.len());
+        remaining[..amt].copy_from_slice(&buf[..amt]);
+        self.offset += amt;
+        Ok(amt)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+macro_rules! cursor_writers {
+    ($(($ty:ident $write:ident))*) => ($(
+        /// Writes `v` in little-endian byte order at the cursor's position and
+        /// advances past it, returning the new position.
+        ///
+        /// # Errors
+        ///
+        /// Returns an error if the value lies out of bounds of the memory, in
+        /// which case nothing is written and the cursor doesn't move.
+        pub fn $write(&mut self, v: $ty) -> Result<usize> {
+            self.write_bytes(&v.to_le_bytes())
+        }
+    )*)
+}
+
+/// A cursor for writing a sequence of fields into a [`Memory`].
+///
+/// Created with [`Memory::cursor`], this tracks the position of the next write
+/// so serializers don't have to compute each field's offset by hand. Unlike
+/// [`MemoryWriter`], writes are typed and never partial: each one is bounds
+/// checked up front and either writes the whole field or fails without
+/// writing anything.
+///
+/// Fields are packed without any padding, and the memory is never grown.
+#[derive(Clone)]
+pub struct MemoryCursor {
+    memory: Memory,
+    position: usize,
+}
+
+impl MemoryCursor {
+    /// Returns the byte offset in the memory that the next write will start
+    /// at.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// Writes `data` at the cursor's position and advances past it, returning
+    /// the new position.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `data` lies out of bounds of the memory, in which
+    /// case nothing is written and the cursor doesn't move.
+    pub fn write_bytes(&mut self, data: &[u8]) -> Result<usize> {
+        self.memory.store_blob(self.position, data, false)?;
+        self.position += data.len();
+        Ok(self.position)
+    }
+
+    /// Writes the utf-8 bytes of `s`, without a length prefix or terminator,
+    /// and advances past them, returning the new position.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `s` lies out of bounds of the memory, in which case
+    /// nothing is written and the cursor doesn't move.
+    pub fn write_str(&mut self, s: &str) -> Result<usize> {
+        self.write_bytes(s.as_bytes())
+    }
+
+    cursor_writers! {
+        (u8 write_u8)
+        (i32 write_i32)
+        (i64 write_i64)
+        (f32 write_f32)
+        (f64 write_f64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn grown_range_checks_len() -> Result<()> {
+        assert_eq!(grown_range(5, 2)?, 3..5);
+        assert_eq!(grown_range(2, 2)?, 0..2);
+        assert_eq!(grown_range(7, 0)?, 7..7);
+
+        // A length smaller than the growth reported by the runtime would have
+        // previously wrapped around or panicked.
+        let err = grown_range(1, 3).unwrap_err();
+        assert_eq!(err.to_string(), "size 1 is too small to have grown by 3");
+        Ok(())
+    }
 }
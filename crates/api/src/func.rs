@@ -1,9 +1,13 @@
-use crate::callable::{NativeCallable, WasmtimeFn, WrappedCallable};
-use crate::{Callable, FuncType, Store, Trap, Val, ValType, AdapterType};
-use anyhow::{ensure, Context as _};
+use crate::callable::{
+    BlockingCallable, IdentityCallable, MutCallable, NativeCallable, NoopCallable, WasmtimeFn,
+    WrappedCallable,
+};
+use crate::runtime::HostState;
+use crate::{AsyncCallable, Callable, Extern, FuncType, Memory, Store, Trap, Val, ValRef, ValType, AdapterType};
+use anyhow::{anyhow, ensure, Context as _};
+use std::convert::TryFrom;
 use std::fmt;
 use std::mem;
-use std::panic::{self, AssertUnwindSafe};
 use std::ptr;
 use std::rc::Rc;
 use wasmtime_runtime::{InstanceHandle, VMContext, VMFunctionBody};
@@ -146,6 +150,7 @@ pub struct Func {
     store: Store,
     callable: Rc<dyn WrappedCallable + 'static>,
     ty: FuncType,
+    interface_ty: Option<AdapterType>,
 }
 
 /// A WebAssembly Adapter which can be called.
@@ -154,6 +159,7 @@ pub struct AdapterFunc {
     store: Store,
     callable: Rc<dyn Callable + 'static>,
     ty: AdapterType,
+    core_func: Option<Func>,
 }
 
 impl AdapterFunc {
@@ -176,9 +182,15 @@ impl AdapterFunc {
             store: store.clone(),
             callable,
             ty,
+            core_func: None,
         }
     }
 
+    pub(crate) fn with_core_func(mut self, core_func: Option<Func>) -> AdapterFunc {
+        self.core_func = core_func;
+        self
+    }
+
     /// Returns the underlying wasm type that this `Adapter` has.
     pub fn ty(&self) -> &AdapterType {
         &self.ty
@@ -197,6 +209,31 @@ impl AdapterFunc {
     pub fn result_arity(&self) -> usize {
         self.ty.results().len()
     }
+
+    /// Returns the core wasm function which this adapter wraps, bypassing any
+    /// interface types marshaling.
+    ///
+    /// This is the function invoked by the adapter's first `call-core`
+    /// instruction, and must be called with the core wasm values that the
+    /// adapter would otherwise produce, which is mostly useful for debugging.
+    ///
+    /// Returns `None` if this adapter wasn't defined by a module, or if it
+    /// doesn't call a core function.
+    pub fn core_func(&self) -> Option<Func> {
+        self.core_func.clone()
+    }
+
+    /// Lowers this adapter into a core wasm [`Func`] with the signature `ty`.
+    ///
+    /// Values passed to the returned `Func` are forwarded to this adapter
+    /// as-is, so `ty` must describe core types that this adapter accepts. The
+    /// returned `Func` remembers this adapter's signature, which is available
+    /// through [`Func::interface_ty`].
+    pub fn lower(&self, ty: FuncType) -> Func {
+        let mut func = Func::new(&self.store, ty, self.callable.clone());
+        func.interface_ty = Some(self.ty.clone());
+        func
+    }
     
     /// Invokes this adapter function
     pub fn call(&self, params: &[Val]) -> Result<Box<[Val]>, Trap> {
@@ -208,9 +245,80 @@ impl AdapterFunc {
             }
         }
         let mut results = vec![Val::null(); self.result_arity()];
-        self.callable.call(params, &mut results)?;
+        if let Err(trap) = self.callable.call(params, &mut results) {
+            self.store.report_trap(&trap);
+            return Err(trap);
+        }
+        Ok(results.into_boxed_slice())
+    }
+
+    /// Invokes this adapter function with borrowed parameters.
+    ///
+    /// This behaves like [`AdapterFunc::call`], except that `string`
+    /// parameters may be passed as [`ValRef::Str`], in which case adapters
+    /// defined by a module copy them straight into wasm memory without first
+    /// allocating an owned `String`.
+    pub fn call_ref(&self, params: &[ValRef<'_>]) -> Result<Box<[Val]>, Trap> {
+        for param in params {
+            if let ValRef::Val(val) = param {
+                if !val.comes_from_same_store(&self.store) {
+                    return Err(Trap::new(
+                        "cross-`Store` values are not currently supported",
+                    ));
+                }
+            }
+        }
+        let mut results = vec![Val::null(); self.result_arity()];
+        if let Err(trap) = self.callable.call_ref(params, &mut results) {
+            self.store.report_trap(&trap);
+            return Err(trap);
+        }
         Ok(results.into_boxed_slice())
     }
+
+    /// Invokes this adapter function like [`AdapterFunc::call`], pairing each
+    /// result with the interface type this adapter declares for it.
+    ///
+    /// The results are checked against the declared types, so callers can
+    /// dispatch on the type tags without having to handle unexpected values.
+    ///
+    /// # Errors
+    ///
+    /// Returns a trap if the call traps, or if a result doesn't have its
+    /// declared type.
+    pub fn call_typed(&self, params: &[Val]) -> Result<Vec<(ValType, Val)>, Trap> {
+        let results = self.call(params)?;
+        let mut typed = Vec::with_capacity(results.len());
+        for (i, (ty, val)) in self.ty.results().iter().zip(results.into_vec()).enumerate() {
+            if val.ty() != *ty {
+                return Err(Trap::new(format!(
+                    "adapter result {} has type {}, but {} was declared",
+                    i,
+                    val.ty(),
+                    ty
+                )));
+            }
+            typed.push((ty.clone(), val));
+        }
+        Ok(typed)
+    }
+
+    /// Invokes this adapter function like [`AdapterFunc::call`], lifting its
+    /// results into the Rust tuple `R`.
+    ///
+    /// Each element of `R` is converted from the corresponding result with
+    /// `TryFrom<Val>`, so for example a `string` result can be received as a
+    /// `String` directly.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the call traps, if it produces a different number
+    /// of results than `R` has elements, or if a result can't be converted to
+    /// the type of its element.
+    pub fn call_typed_out<R: AdapterResults>(&self, params: &[Val]) -> anyhow::Result<R> {
+        let results = self.call(params)?;
+        R::from_results(results.into_vec())
+    }
 }
 
 macro_rules! wrappers {
@@ -238,14 +346,14 @@ macro_rules! wrappers {
             {
                 let ret = {
                     let instance = InstanceHandle::from_vmctx(vmctx);
-                    let func = instance.host_state().downcast_ref::<F>().expect("state");
-                    panic::catch_unwind(AssertUnwindSafe(|| {
-                        func($($args::from_abi(_caller_vmctx, $args)),*)
-                    }))
+                    let func = HostState::of(&instance).downcast_ref::<F>().expect("state");
+                    Trap::catch_panic(|| func($($args::from_abi(_caller_vmctx, $args)),*))
                 };
+                // Panics are reported as traps just like they are for host
+                // functions created with `Func::new`, see `stub_fn`.
                 match ret {
                     Ok(ret) => ret.into_abi(),
-                    Err(panic) => wasmtime_runtime::resume_panic(panic),
+                    Err(trap) => wasmtime_runtime::raise_user_trap(Box::new(trap)),
                 }
             }
 
@@ -378,6 +486,73 @@ impl Func {
         Func::from_wrapped(store, ty, callable)
     }
 
+    /// Creates a new `Func` with the given signature `ty`, implemented by the
+    /// [`AsyncCallable`] `callable`.
+    ///
+    /// There's no integration with an async executor: each call blocks the
+    /// calling thread until the future returned by `callable` completes,
+    /// parking the thread whenever it's pending. Futures which can only make
+    /// progress when polled by an executor running on the same thread will
+    /// therefore deadlock.
+    pub fn new_async(
+        store: &Store,
+        ty: FuncType,
+        callable: Rc<dyn AsyncCallable + 'static>,
+    ) -> Self {
+        Func::new(store, ty, Rc::new(BlockingCallable::new(callable)))
+    }
+
+    /// Creates a new `Func` from a uniquely-owned `callable`, like
+    /// [`Func::new`].
+    ///
+    /// This is useful when the [`Callable`] isn't shared with anything else,
+    /// and avoids having to wrap it in an `Rc` first.
+    pub fn new_boxed(store: &Store, ty: FuncType, callable: Box<dyn Callable + 'static>) -> Self {
+        Func::new(store, ty, Rc::from(callable))
+    }
+
+    /// Creates a new `Func` with the given signature `ty`, implemented by the
+    /// closure `func`, which may mutate the state it captures.
+    ///
+    /// Calls are dynamically checked to make sure the closure is never run
+    /// reentrantly, for example when wasm called by the closure calls back
+    /// into this `Func`. Such a call produces a [`Trap`] rather than aliasing the
+    /// closure's state or panicking.
+    pub fn new_mut(
+        store: &Store,
+        ty: FuncType,
+        func: impl FnMut(&[Val], &mut [Val]) -> Result<(), Trap> + 'static,
+    ) -> Self {
+        Func::new(store, ty, Rc::new(MutCallable::new(func)))
+    }
+
+    /// Creates a new `Func` with the signature `ty` which ignores its
+    /// parameters and returns the default value of each of its result types,
+    /// as given by [`Val::default_for`].
+    ///
+    /// This is mostly useful as a placeholder import, for example in tests.
+    pub fn noop(store: &Store, ty: FuncType) -> Self {
+        let callable = NoopCallable::new(&ty);
+        Func::new(store, ty, Rc::new(callable))
+    }
+
+    /// Creates a new `Func` with the signature `ty` which returns its
+    /// parameters unchanged as its results.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the result types of `ty` aren't the same as its
+    /// parameter types.
+    pub fn identity(store: &Store, ty: FuncType) -> anyhow::Result<Self> {
+        ensure!(
+            ty.params() == ty.results(),
+            "identity function must have matching params and results, got {:?} -> {:?}",
+            ty.params(),
+            ty.results()
+        );
+        Ok(Func::new(store, ty, Rc::new(IdentityCallable)))
+    }
+
     wrappers! {
         /// Creates a new `Func` from the given Rust closure, which takes 0
         /// arguments.
@@ -594,6 +769,29 @@ impl Func {
         (wrap15, A1, A2, A3, A4, A5, A6, A7, A8, A9, A10, A11, A12, A13, A14, A15)
     }
 
+    /// Creates a new `Func` from the given Rust closure, inferring its
+    /// signature from the closure's parameter and return types.
+    ///
+    /// This is the same as calling the [`Func::wrap1`] family of functions,
+    /// except that the number of arguments doesn't need to be spelled out, so
+    /// the same types are supported. Closures may take up to 15 arguments.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use wasmtime::*;
+    /// # fn main() -> anyhow::Result<()> {
+    /// # let store = Store::default();
+    /// let add = Func::wrap(&store, |a: i32, b: i32| a + b);
+    /// assert_eq!(add.ty().params(), &[ValType::I32, ValType::I32]);
+    /// assert_eq!(add.ty().results(), &[ValType::I32]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn wrap<Params, Results>(store: &Store, func: impl IntoFunc<Params, Results>) -> Func {
+        func.into_func(store)
+    }
+
     fn from_wrapped(
         store: &Store,
         ty: FuncType,
@@ -603,6 +801,7 @@ impl Func {
             store: store.clone(),
             callable,
             ty,
+            interface_ty: None,
         }
     }
 
@@ -611,6 +810,15 @@ impl Func {
         &self.ty
     }
 
+    /// Returns the interface types signature of the adapter this `Func` was
+    /// lowered from, if any.
+    ///
+    /// This is only `Some` for functions created with [`AdapterFunc::lower`],
+    /// and is `None` for all plain core wasm and host functions.
+    pub fn interface_ty(&self) -> Option<&AdapterType> {
+        self.interface_ty.as_ref()
+    }
+
     /// Returns the number of parameters that this function takes.
     pub fn param_arity(&self) -> usize {
         self.ty.params().len()
@@ -628,8 +836,8 @@ impl Func {
     /// trap will occur. If a trap occurs while executing this function, then a
     /// trap will also be returned.
     ///
-    /// This function should not panic unless the underlying function itself
-    /// initiates a panic.
+    /// If the underlying function is a host function which panics, or calls a
+    /// host function which panics, the panic is returned as a trap as well.
     pub fn call(&self, params: &[Val]) -> Result<Box<[Val]>, Trap> {
         for param in params {
             if !param.comes_from_same_store(&self.store) {
@@ -643,6 +851,143 @@ impl Func {
         Ok(results.into_boxed_slice())
     }
 
+    /// Invokes this function with the `params` given, like [`Func::call`], but
+    /// writes the results into `results` instead of allocating a new buffer.
+    ///
+    /// `results` is resized to [`Func::result_arity`] and its entries are
+    /// overwritten, so the same buffer can be reused across many calls.
+    pub fn call_into(&self, params: &[Val], results: &mut Vec<Val>) -> Result<(), Trap> {
+        for param in params {
+            if !param.comes_from_same_store(&self.store) {
+                return Err(Trap::new(
+                    "cross-`Store` values are not currently supported",
+                ));
+            }
+        }
+        results.resize(self.result_arity(), Val::null());
+        self.callable.call(params, results)
+    }
+
+    /// Invokes this function with the arguments produced by `args`, like
+    /// [`Func::call`].
+    ///
+    /// This is a convenience for building arguments lazily, for example with
+    /// an iterator adapter, without collecting them into a slice first.
+    pub fn call_iter(&self, args: impl IntoIterator<Item = Val>) -> Result<Box<[Val]>, Trap> {
+        let params = args.into_iter().collect::<Vec<_>>();
+        self.call(&params)
+    }
+
+    /// Invokes this function with the `params` given, like [`Func::call`], but
+    /// without checking that `params` match the type signature of this `Func`.
+    ///
+    /// This is intended for hot paths, such as benchmarks, which have already
+    /// validated the types of their arguments.
+    ///
+    /// # Safety
+    ///
+    /// The number and types of `params` must exactly match
+    /// [`Func::ty`]'s parameters, and all values must come from this
+    /// function's [`Store`]. Passing anything else will hand wasm code
+    /// mistyped values, which is undefined behavior.
+    pub unsafe fn call_unchecked(&self, params: &[Val]) -> Result<Box<[Val]>, Trap> {
+        let mut results = vec![Val::null(); self.result_arity()];
+        self.callable.call_unchecked(params, &mut results)?;
+        Ok(results.into_boxed_slice())
+    }
+
+    /// Invokes this function once for each `(params, results)` pair in
+    /// `batches`, writing the results of each invocation into the provided
+    /// buffer instead of allocating a new one per call.
+    ///
+    /// Each `results` buffer must have a length of exactly
+    /// [`Func::result_arity`]. Invocations happen in order and stop at the first
+    /// trap, which is then returned; results of earlier invocations are left in
+    /// their buffers.
+    pub fn call_batch(&self, batches: &mut [(&[Val], &mut [Val])]) -> Result<(), Trap> {
+        for (params, results) in batches.iter() {
+            if results.len() != self.result_arity() {
+                return Err(Trap::new(format!(
+                    "expected {} results, got {}",
+                    self.result_arity(),
+                    results.len()
+                )));
+            }
+            for param in params.iter() {
+                if !param.comes_from_same_store(&self.store) {
+                    return Err(Trap::new(
+                        "cross-`Store` values are not currently supported",
+                    ));
+                }
+            }
+        }
+        self.callable.call_batch(batches)
+    }
+
+    /// Invokes this function, which must return a single `i32` offset into
+    /// `memory`, and reads the NUL-terminated UTF-8 string found there.
+    ///
+    /// This is a shorthand for the common pattern of an export returning a
+    /// pointer to a string in its instance's memory.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if this function doesn't return exactly one `i32`, if
+    /// the call traps, or if the returned offset doesn't point at a
+    /// NUL-terminated string of valid UTF-8 within `memory`.
+    pub fn call_returning_cstr(&self, params: &[Val], memory: &Memory) -> anyhow::Result<String> {
+        ensure!(
+            self.ty.results() == [ValType::I32],
+            "function returns {:?}, expected a single i32",
+            self.ty.results()
+        );
+        let offset = self.call(params)?[0].unwrap_i32() as u32 as usize;
+        let data = unsafe { memory.data_unchecked() };
+        let bytes = data
+            .get(offset..)
+            .ok_or_else(|| anyhow!("string offset {} is out of bounds", offset))?;
+        let len = bytes
+            .iter()
+            .position(|b| *b == 0)
+            .ok_or_else(|| anyhow!("string at offset {} is not NUL-terminated", offset))?;
+        let s = std::str::from_utf8(&bytes[..len])
+            .with_context(|| format!("string at offset {} is not valid UTF-8", offset))?;
+        Ok(s.to_string())
+    }
+
+    /// Returns the address, in the host's address space, of the native code
+    /// backing this function.
+    ///
+    /// This is primarily intended for integration with external profilers and
+    /// debuggers, for example to symbolicate samples or set breakpoints.
+    ///
+    /// Returns `None` if this function is implemented by a host [`Callable`]
+    /// created through [`Func::new`], since there's no compiled code to point
+    /// at in that case.
+    pub fn native_address(&self) -> Option<usize> {
+        self.callable.native_address()
+    }
+
+    /// Returns an address which identifies this function and all of its
+    /// clones, used to represent it in reference-typed globals.
+    pub(crate) fn addr(&self) -> usize {
+        &*self.callable as *const dyn WrappedCallable as *const u8 as usize
+    }
+
+    /// Creates a copy of this function in `store`, sharing the same host
+    /// implementation.
+    ///
+    /// This is only possible for functions implemented by a host [`Callable`]
+    /// created through [`Func::new`]. Returns `None` for functions defined by
+    /// wasm modules, which are tied to the instance they came from, as well as
+    /// for functions created through [`Func::wrap1`] and friends.
+    pub fn clone_into(&self, store: &Store) -> Option<Func> {
+        let callable = self.callable.host_callable()?.clone();
+        let mut func = Func::new(store, self.ty.clone(), callable);
+        func.interface_ty = self.interface_ty.clone();
+        Some(func)
+    }
+
     pub(crate) fn wasmtime_function(&self) -> &wasmtime_runtime::ExportFunction {
         self.callable.wasmtime_function()
     }
@@ -804,6 +1149,44 @@ impl Func {
     }
 }
 
+/// A handle to the instance which called into a host function, passed to
+/// [`Callable::call_with_caller`].
+///
+/// This is primarily used to access the calling instance's exports, such as
+/// its linear memory, while a host function is running.
+pub struct Caller<'a> {
+    store: &'a Store,
+    caller_vmctx: *mut VMContext,
+}
+
+impl<'a> Caller<'a> {
+    pub(crate) fn new(store: &'a Store, caller_vmctx: *mut VMContext) -> Caller<'a> {
+        Caller {
+            store,
+            caller_vmctx,
+        }
+    }
+
+    /// Looks up an export of the calling instance by `name`.
+    ///
+    /// Returns `None` if the caller has no export named `name`, or if there is
+    /// no calling wasm instance, for example when the host function was
+    /// invoked directly through [`Func::call`].
+    pub fn get_export(&self, name: &str) -> Option<Extern> {
+        if self.caller_vmctx.is_null() {
+            return None;
+        }
+        let instance = unsafe { InstanceHandle::from_vmctx(self.caller_vmctx) };
+        let export = instance.lookup(name)?;
+        Some(Extern::from_wasmtime_export(self.store, instance, export))
+    }
+
+    /// Returns the [`Store`] that the host function being called belongs to.
+    pub fn store(&self) -> &Store {
+        self.store
+    }
+}
+
 impl fmt::Debug for Func {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "Func")
@@ -1060,3 +1443,93 @@ impl<T: WasmTy> WasmRet for Result<T, Trap> {
         T::store(abi, ptr);
     }
 }
+
+/// A trait implemented for tuples of Rust types which the results of an
+/// adapter can be lifted into with [`AdapterFunc::call_typed_out`].
+///
+/// This trait should not be implemented by user types. It's implemented for
+/// tuples of up to 8 elements whose types implement `TryFrom<Val>`.
+pub trait AdapterResults: Sized {
+    #[doc(hidden)]
+    fn from_results(results: Vec<Val>) -> anyhow::Result<Self>;
+}
+
+macro_rules! adapter_results {
+    ($(($n:tt $($results:ident)*))*) => ($(
+        impl<$($results,)*> AdapterResults for ($($results,)*)
+        where
+            $($results: TryFrom<Val, Error = anyhow::Error>,)*
+        {
+            fn from_results(results: Vec<Val>) -> anyhow::Result<Self> {
+                ensure!(
+                    results.len() == $n,
+                    "expected {} results, adapter produced {}",
+                    $n,
+                    results.len()
+                );
+                let _results = &mut results.into_iter().enumerate();
+                Ok(($({
+                    let (i, val) = _results.next().unwrap();
+                    <$results as TryFrom<Val>>::try_from(val)
+                        .with_context(|| format!("failed to lift adapter result {}", i))?
+                },)*))
+            }
+        }
+    )*)
+}
+
+adapter_results! {
+    (0)
+    (1 A1)
+    (2 A1 A2)
+    (3 A1 A2 A3)
+    (4 A1 A2 A3 A4)
+    (5 A1 A2 A3 A4 A5)
+    (6 A1 A2 A3 A4 A5 A6)
+    (7 A1 A2 A3 A4 A5 A6 A7)
+    (8 A1 A2 A3 A4 A5 A6 A7 A8)
+}
+
+/// A trait implemented for closures which can be passed to [`Func::wrap`].
+///
+/// This trait should not be implemented by user types. It's implemented for
+/// all closures which could be passed to [`Func::wrap1`] and friends, with
+/// `Params` being the tuple of the closure's argument types.
+pub trait IntoFunc<Params, Results> {
+    #[doc(hidden)]
+    fn into_func(self, store: &Store) -> Func;
+}
+
+macro_rules! into_funcs {
+    ($(($name:ident $(,$args:ident)*))*) => ($(
+        impl<F, $($args,)* R> IntoFunc<($($args,)*), R> for F
+        where
+            F: Fn($($args),*) -> R + 'static,
+            $($args: WasmTy,)*
+            R: WasmRet,
+        {
+            fn into_func(self, store: &Store) -> Func {
+                Func::$name(store, self)
+            }
+        }
+    )*)
+}
+
+into_funcs! {
+    (wrap0)
+    (wrap1, A1)
+    (wrap2, A1, A2)
+    (wrap3, A1, A2, A3)
+    (wrap4, A1, A2, A3, A4)
+    (wrap5, A1, A2, A3, A4, A5)
+    (wrap6, A1, A2, A3, A4, A5, A6)
+    (wrap7, A1, A2, A3, A4, A5, A6, A7)
+    (wrap8, A1, A2, A3, A4, A5, A6, A7, A8)
+    (wrap9, A1, A2, A3, A4, A5, A6, A7, A8, A9)
+    (wrap10, A1, A2, A3, A4, A5, A6, A7, A8, A9, A10)
+    (wrap11, A1, A2, A3, A4, A5, A6, A7, A8, A9, A10, A11)
+    (wrap12, A1, A2, A3, A4, A5, A6, A7, A8, A9, A10, A11, A12)
+    (wrap13, A1, A2, A3, A4, A5, A6, A7, A8, A9, A10, A11, A12, A13)
+    (wrap14, A1, A2, A3, A4, A5, A6, A7, A8, A9, A10, A11, A12, A13, A14)
+    (wrap15, A1, A2, A3, A4, A5, A6, A7, A8, A9, A10, A11, A12, A13, A14, A15)
+}
@@ -1,10 +1,15 @@
-use crate::externals::Extern;
+use crate::externals::{Extern, Global, Memory, Table};
+use crate::func::{AdapterFunc, Func};
 use crate::module::Module;
-use crate::runtime::{Config, Store};
+use crate::runtime::{Config, HostState, Store};
 use crate::trap::Trap;
+use crate::{ExternType, Mutability, Val};
 use anyhow::{bail, Error, Result};
+use std::sync::atomic::{AtomicU64, Ordering::SeqCst};
 use wasmtime_jit::{CompiledModule, Resolver};
-use wasmtime_runtime::{Export, InstanceHandle, InstantiationError, SignatureRegistry};
+use wasmtime_runtime::{
+    Export, InstanceHandle, InstantiationError, SignatureRegistry, VMInterrupts,
+};
 
 struct SimpleResolver<'a> {
     imports: &'a [Extern],
@@ -23,6 +28,8 @@ fn instantiate(
     compiled_module: &CompiledModule,
     imports: &[Extern],
     sig_registry: &SignatureRegistry,
+    host_state: HostState,
+    interrupts: *const VMInterrupts,
 ) -> Result<InstanceHandle, Error> {
     let mut resolver = SimpleResolver { imports };
     unsafe {
@@ -31,6 +38,8 @@ fn instantiate(
                 config.validating_config.operator_config.enable_bulk_memory,
                 &mut resolver,
                 sig_registry,
+                Box::new(host_state),
+                interrupts,
             )
             .map_err(|e| -> Error {
                 match e {
@@ -44,6 +53,42 @@ fn instantiate(
     }
 }
 
+/// Finds the first entry in `imports` which doesn't match the corresponding
+/// import of `module`, returning a description naming that import.
+fn describe_import_mismatch(module: &Module, imports: &[Extern]) -> Option<String> {
+    module
+        .imports()
+        .iter()
+        .zip(imports)
+        .find_map(|(expected, import)| import.matches_import(expected).err())
+}
+
+/// A unique identifier for an [`Instance`].
+///
+/// Every instance created with [`Instance::new`] is assigned a new id, which
+/// increases monotonically over the lifetime of the process. Use
+/// [`Extern::origin`] to find out which instance an export came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct InstanceId(u64);
+
+impl InstanceId {
+    fn next() -> InstanceId {
+        static NEXT: AtomicU64 = AtomicU64::new(0);
+        InstanceId(NEXT.fetch_add(1, SeqCst))
+    }
+
+    /// Returns the id of the instance owning `handle`, or `None` if it wasn't
+    /// created by [`Instance::new`].
+    pub(crate) fn of(handle: &InstanceHandle) -> Option<InstanceId> {
+        HostState::of(handle).downcast_ref::<InstanceId>().copied()
+    }
+
+    /// Returns this id as an integer, for example to include it in logs.
+    pub fn as_u64(&self) -> u64 {
+        self.0
+    }
+}
+
 /// An instantiated WebAssembly module.
 ///
 /// This type represents the instantiation of a [`Module`]. Once instantiated
@@ -123,20 +168,65 @@ impl Instance {
             }
         }
 
+        // Account for the initial size of the memories the module defines
+        // against the store's limit; imported memories are already counted.
+        let local = &module.compiled_module().module_ref().local;
+        let memory_bytes = local
+            .memory_plans
+            .values()
+            .skip(local.num_imported_memories)
+            .map(|plan| plan.memory.minimum as usize * Memory::PAGE_SIZE)
+            .sum();
+        let reservation = store.reserve_memory(memory_bytes)?;
+        let mut state = store
+            .instance_state(Box::new(InstanceId::next()))?
+            .with_memory(reservation);
+
+        // Wasm code may store references in the reference-typed globals the
+        // module defines, so those need to stay alive with the instance.
+        let defines_ref_globals = local
+            .globals
+            .values()
+            .skip(local.num_imported_globals)
+            .any(|global| global.ty.is_ref());
+        if defines_ref_globals {
+            state = state.with_global_refs(store.global_refs());
+        }
+
         let config = store.engine().config();
         let instance_handle = instantiate(
             config,
             module.compiled_module(),
             imports,
             store.compiler().signatures(),
-        )?;
+            state,
+            store.interrupts(),
+        )
+        .map_err(|e| match e.downcast_ref::<InstantiationError>() {
+            Some(InstantiationError::Link(_)) => match describe_import_mismatch(module, imports) {
+                Some(reason) => e.context(reason),
+                None => e,
+            },
+            _ => e,
+        })?;
+        HostState::register_ref_globals(&instance_handle);
 
         let mut exports = Vec::with_capacity(module.exports().len());
         for export in module.exports() {
             let export = match module.inner.export_map[export.name()] {
                 crate::module::Export::Core => {
-                    let export = instance_handle.lookup(export.name()).expect("export");
-                    Extern::from_wasmtime_export(store, instance_handle.clone(), export)
+                    let wasmtime_export = instance_handle.lookup(export.name()).expect("export");
+                    let ext = Extern::from_wasmtime_export(
+                        store,
+                        instance_handle.clone(),
+                        wasmtime_export,
+                    );
+                    match (ext, export.ty()) {
+                        (Extern::Global(global), ExternType::Global(ty)) => {
+                            Extern::Global(global.with_declared_ty(ty))
+                        }
+                        (ext, _) => ext,
+                    }
                 }
                 crate::module::Export::Adapter(idx) => {
                     let func = Module::adapter(module, instance_handle.clone(), idx);
@@ -170,6 +260,11 @@ impl Instance {
         &self.module
     }
 
+    /// Returns the unique id of this instance.
+    pub fn id(&self) -> InstanceId {
+        InstanceId::of(&self.instance_handle).expect("instances always have an id")
+    }
+
     /// Returns the list of exported items from this [`Instance`].
     ///
     /// Note that the exports here do not have names associated with them,
@@ -181,6 +276,34 @@ impl Instance {
         &self.exports
     }
 
+    /// Returns an iterator over the exported items from this [`Instance`],
+    /// along with the name of each export.
+    ///
+    /// Exports are yielded in the same order as [`Instance::exports`] and
+    /// [`Module::exports`].
+    pub fn iter_exports(&self) -> impl Iterator<Item = (&str, Extern)> {
+        self.module
+            .exports()
+            .iter()
+            .zip(self.exports.iter())
+            .map(|(ty, export)| (ty.name(), export.clone()))
+    }
+
+    /// Returns the exported items from this [`Instance`] along with their
+    /// names, sorted by name.
+    ///
+    /// Unlike [`Instance::exports`], the order here doesn't depend on the
+    /// order in which the module declared its exports, which makes it useful
+    /// for producing stable output such as in snapshot tests.
+    pub fn sorted_exports(&self) -> Vec<(String, Extern)> {
+        let mut exports = self
+            .iter_exports()
+            .map(|(name, export)| (name.to_string(), export))
+            .collect::<Vec<_>>();
+        exports.sort_by(|(a, _), (b, _)| a.cmp(b));
+        exports
+    }
+
     /// Looks up an exported [`Extern`] value by name.
     ///
     /// This method will search the module for an export named `name` and return
@@ -197,6 +320,96 @@ impl Instance {
         Some(&self.exports()[i])
     }
 
+    /// Looks up an exported [`Func`] value by name.
+    ///
+    /// Returns `None` if there was no export named `name`, or if it's not a
+    /// function.
+    pub fn get_func(&self, name: &str) -> Option<Func> {
+        self.get_export(name)?.func().cloned()
+    }
+
+    /// Looks up an exported [`AdapterFunc`] value by name.
+    ///
+    /// Returns `None` if there was no export named `name`, or if it's not a
+    /// adapter function.
+    pub fn get_adapter(&self, name: &str) -> Option<AdapterFunc> {
+        self.get_export(name)?.adapter().cloned()
+    }
+
+    /// Looks up an exported [`Memory`] value by name.
+    ///
+    /// Returns `None` if there was no export named `name`, or if it's not a
+    /// memory.
+    pub fn get_memory(&self, name: &str) -> Option<Memory> {
+        self.get_export(name)?.memory().cloned()
+    }
+
+    /// Looks up an exported [`Global`] value by name.
+    ///
+    /// Returns `None` if there was no export named `name`, or if it's not a
+    /// global.
+    pub fn get_global(&self, name: &str) -> Option<Global> {
+        self.get_export(name)?.global().cloned()
+    }
+
+    /// Looks up an exported [`Table`] value by name.
+    ///
+    /// Returns `None` if there was no export named `name`, or if it's not a
+    /// table.
+    pub fn get_table(&self, name: &str) -> Option<Table> {
+        self.get_export(name)?.table().cloned()
+    }
+
+    /// Returns the current values of all mutable globals exported by this
+    /// instance.
+    ///
+    /// Values are listed in the order of [`Instance::exports`], skipping all
+    /// exports which aren't mutable globals. The result can be passed to
+    /// [`Instance::restore_globals`] to reset the globals later on, for
+    /// example to replay execution deterministically.
+    pub fn snapshot_globals(&self) -> Vec<Val> {
+        self.mutable_globals().map(|g| g.get()).collect()
+    }
+
+    /// Sets the mutable globals exported by this instance to `vals`, which
+    /// were previously returned by [`Instance::snapshot_globals`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `vals` doesn't have one value of the right type for
+    /// each mutable global. No globals are modified in that case.
+    pub fn restore_globals(&self, vals: &[Val]) -> Result<()> {
+        let globals = self.mutable_globals().collect::<Vec<_>>();
+        if globals.len() != vals.len() {
+            bail!(
+                "expected {} global values, got {}",
+                globals.len(),
+                vals.len()
+            );
+        }
+        for (i, (global, val)) in globals.iter().zip(vals).enumerate() {
+            if val.ty() != *global.ty().content() {
+                bail!(
+                    "global value {} should be {}, got {}",
+                    i,
+                    global.ty().content(),
+                    val.ty()
+                );
+            }
+        }
+        for (global, val) in globals.iter().zip(vals) {
+            global.set(val.clone())?;
+        }
+        Ok(())
+    }
+
+    fn mutable_globals(&self) -> impl Iterator<Item = &Global> {
+        self.exports
+            .iter()
+            .filter_map(|e| e.global())
+            .filter(|g| g.ty().mutability() == Mutability::Var)
+    }
+
     #[doc(hidden)]
     pub fn handle(&self) -> &InstanceHandle {
         &self.instance_handle
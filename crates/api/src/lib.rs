@@ -21,15 +21,15 @@ mod trap;
 mod types;
 mod values;
 
-pub use crate::callable::Callable;
+pub use crate::callable::{AsyncCallable, Callable, CallableOwned, OwnedCallable};
 pub use crate::externals::*;
 pub use crate::frame_info::FrameInfo;
-pub use crate::func::{Func, WasmRet, WasmTy, AdapterFunc};
-pub use crate::instance::Instance;
+pub use crate::func::{AdapterResults, Caller, Func, IntoFunc, WasmRet, WasmTy, AdapterFunc};
+pub use crate::instance::{Instance, InstanceId};
 pub use crate::module::Module;
 pub use crate::r#ref::{AnyRef, HostInfo, HostRef};
 pub use crate::runtime::{Config, Engine, OptLevel, Store, Strategy};
-pub use crate::trap::Trap;
+pub use crate::trap::{Trap, TrapKind};
 pub use crate::types::*;
 pub use crate::values::*;
 
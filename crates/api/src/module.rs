@@ -4,7 +4,7 @@ use crate::types::{
     ExportType, ExternType, FuncType, GlobalType, ImportType, Limits, MemoryType, Mutability,
     TableType, ValType, AdapterType
 };
-use crate::{Callable, Store, Trap, Val, AdapterFunc, Memory};
+use crate::{AdapterFunc, Callable, Extern, Func, Memory, Store, Trap, Val, ValRef};
 use anyhow::{bail, Result};
 use std::collections::HashMap;
 use std::convert::TryInto;
@@ -58,7 +58,7 @@ fn into_func_type(mt: wasmparser::FuncType) -> FuncType {
     assert_eq!(mt.form, wasmparser::Type::Func);
     let params = mt.params.iter().map(into_valtype).collect::<Vec<_>>();
     let returns = mt.returns.iter().map(into_valtype).collect::<Vec<_>>();
-    FuncType::new(params.into_boxed_slice(), returns.into_boxed_slice())
+    FuncType::from_vecs(params, returns)
 }
 
 fn into_table_type(tt: wasmparser::TableType) -> TableType {
@@ -543,6 +543,26 @@ impl Module {
         &self.inner.imports
     }
 
+    /// Returns the imports of this [`Module`] which are not satisfied by
+    /// `provided`.
+    ///
+    /// Imports are matched positionally, just like in
+    /// [`Instance::new`](crate::Instance::new): an import is missing if
+    /// `provided` has no entry at its position, or if that entry doesn't
+    /// match the import's type as checked by [`Extern::matches_import`].
+    /// Entries of `provided` beyond the number of imports are ignored.
+    pub fn missing_imports(&self, provided: &[Extern]) -> Vec<ImportType> {
+        self.imports()
+            .iter()
+            .enumerate()
+            .filter(|(i, import)| match provided.get(*i) {
+                Some(ext) => ext.matches_import(import).is_err(),
+                None => true,
+            })
+            .map(|(_, import)| import.clone())
+            .collect()
+    }
+
     /// Returns the list of exports that this [`Module`] has and will be
     /// available after instantiation.
     ///
@@ -605,13 +625,21 @@ impl Module {
         &self.inner.store
     }
 
-    /// Returns the length of the adapter
-    pub fn adapters(&self) -> usize {
-        for elem in self.inner.adapters.iter() {
-            println!("{:#?}", elem);
-        }
-        let size = &self.inner.adapters.len();
-        *size
+    /// Returns the name and interface signature of each adapter function
+    /// exported by this [`Module`].
+    ///
+    /// Adapters are only present in modules using the interface types
+    /// proposal, and are listed in the same order as they appear in
+    /// [`Module::exports`].
+    pub fn adapters(&self) -> Vec<(String, AdapterType)> {
+        self.inner
+            .exports
+            .iter()
+            .filter_map(|export| match export.ty() {
+                ExternType::Adapter(ty) => Some((export.name().to_string(), ty.clone())),
+                _ => None,
+            })
+            .collect()
     }
 
     /// Register this module's stack frame information into the global scope.
@@ -929,13 +957,30 @@ impl Module {
 
     pub(crate) fn adapter(module: &Self, instance: InstanceHandle, idx: usize) -> AdapterFunc {
         let ty = module.inner.adapters[idx].0.clone();
+        let core_func = match &module.inner.adapters[idx].1 {
+            Adapter::Local(instrs) => instrs.iter().find_map(|instr| match instr {
+                wit_parser::Instruction::CallCore(f) => {
+                    let export = wasmtime_environ::Export::Function(FuncIndex::from_u32(*f));
+                    match instance.clone().lookup_by_declaration(&export) {
+                        rtExport::Function(f) => Some(Func::from_wasmtime_function(
+                            f,
+                            &module.inner.store,
+                            instance.clone(),
+                        )),
+                        _ => None,
+                    }
+                }
+                _ => None,
+            }),
+            Adapter::Import(_) => None,
+        };
         let callable = Rc::new(CallAdapter {
             module: module.clone(),
             idx,
             instance,
         });
         // Func::new(&module.inner.store, ty, callable)
-        AdapterFunc::new(&module.inner.store, ty, callable)
+        AdapterFunc::new(&module.inner.store, ty, callable).with_core_func(core_func)
     }
 }
 
@@ -947,6 +992,11 @@ struct CallAdapter {
 
 impl Callable for CallAdapter {
     fn call(&self, params: &[Val], results: &mut [Val]) -> Result<(), Trap> {
+        let params = params.iter().map(ValRef::Val).collect::<Vec<_>>();
+        self.call_ref(&params, results)
+    }
+
+    fn call_ref(&self, params: &[ValRef<'_>], results: &mut [Val]) -> Result<(), Trap> {
         let (ty, adapter) = &self.module.inner.adapters[self.idx];
         let ty_params = ty.params();
         if params.len() != ty_params.len() {
@@ -978,7 +1028,7 @@ impl Callable for CallAdapter {
         // Note here `stack` is a runtime stack 
         // while `finally` is for the deferred instructions 
         // which would be executed at the end of our adapter call
-        let mut stack = Vec::new();
+        let mut stack = Stack::default();
         let mut finally = Vec::new();
         match adapter {
             Adapter::Local(instrs) => {
@@ -1002,21 +1052,90 @@ impl Callable for CallAdapter {
                 }, 
                 _ => (),
             }
-            for param in params {
-                stack.push(param);
-            }
+            stack.extend(params);
             self.execute(&mut stack, &[], &instr, &mut finally)?;
         }
 
         // should be true because of validation
         assert_eq!(stack.len(), results.len());
-        for (item, slot) in stack.into_iter().zip(results) {
-            *slot = item;
+        for (item, slot) in stack.items.into_iter().zip(results) {
+            *slot = item.into_val();
         }
         Ok(())
     }
 }
 
+/// A value on the stack of an adapter being executed.
+///
+/// `string` arguments are borrowed rather than copied onto the stack, since
+/// they're typically only written straight into wasm memory.
+enum StackVal<'a> {
+    Val(Val),
+    Str(&'a str),
+}
+
+impl StackVal<'_> {
+    fn ty(&self) -> ValType {
+        match self {
+            StackVal::Val(val) => val.ty(),
+            StackVal::Str(_) => ValType::String,
+        }
+    }
+
+    fn into_val(self) -> Val {
+        match self {
+            StackVal::Val(val) => val,
+            StackVal::Str(s) => Val::String(s.to_string()),
+        }
+    }
+}
+
+#[derive(Default)]
+struct Stack<'a> {
+    items: Vec<StackVal<'a>>,
+}
+
+impl<'a> Stack<'a> {
+    fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    fn push(&mut self, val: Val) {
+        self.items.push(StackVal::Val(val));
+    }
+
+    fn push_arg(&mut self, arg: ValRef<'a>) {
+        self.items.push(match arg {
+            ValRef::Val(Val::String(s)) => StackVal::Str(s),
+            ValRef::Val(val) => StackVal::Val(val.clone()),
+            ValRef::Str(s) => StackVal::Str(s),
+        });
+    }
+
+    fn pop(&mut self) -> Option<StackVal<'a>> {
+        self.items.pop()
+    }
+
+    fn extend(&mut self, vals: impl IntoIterator<Item = Val>) {
+        self.items.extend(vals.into_iter().map(StackVal::Val));
+    }
+
+    fn truncate(&mut self, len: usize) {
+        self.items.truncate(len);
+    }
+
+    /// Copies the values from `start` to the top of the stack.
+    fn to_vals(&self, start: usize) -> Vec<Val> {
+        self.items[start..]
+            .iter()
+            .map(|item| match item {
+                StackVal::Val(val) => val.clone(),
+                StackVal::Str(s) => Val::String(s.to_string()),
+            })
+            .collect()
+    }
+}
+
 
 /// Function used for test, 
 /// print adapter function stack
@@ -1044,7 +1163,7 @@ impl CallAdapter {
         Ok(())
     }
 
-    fn callcore(&self, f: &u32, stack: &mut Vec<Val>) -> Result<(), Trap> {
+    fn callcore(&self, f: &u32, stack: &mut Stack<'_>) -> Result<(), Trap> {
         let idx = FuncIndex::from_u32(*f);
         let sigidx = self.instance.module().local.functions[idx];
         let sig = &self.instance.module().local.signatures[sigidx];
@@ -1059,7 +1178,7 @@ impl CallAdapter {
             let params_start = stack.len() + 2 - sig.params.len();
             
             WasmtimeFn::new(&self.module.inner.store, self.instance.clone(), export, trampoline)
-                .call(&stack[params_start..], &mut ret)?;
+                .call(&stack.to_vals(params_start), &mut ret)?;
 
             stack.truncate(params_start);
             stack.extend(ret);
@@ -1094,23 +1213,27 @@ impl CallAdapter {
         }
     }
 
-    fn execute(
+    fn execute<'a>(
         &self,
-        stack: &mut Vec<Val>,
-        args: &[Val],
+        stack: &mut Stack<'a>,
+        args: &[ValRef<'a>],
         instr: &wit_parser::Instruction,
         finally: &mut Vec<(wit_parser::Instruction, Vec<Val>)>, 
     ) -> Result<(), Trap> {
         use wit_parser::{Instruction::*};
 
-        fn pop(stack: &mut Vec<Val>, ty: ValType) -> Val {
+        fn pop_item<'a>(stack: &mut Stack<'a>, ty: ValType) -> StackVal<'a> {
             let ret = stack.pop().unwrap();
             assert_eq!(ret.ty(), ty);
             return ret;
         }
 
+        fn pop(stack: &mut Stack<'_>, ty: ValType) -> Val {
+            pop_item(stack, ty).into_val()
+        }
+
         match instr {
-            ArgGet(arg) => stack.push(args[*arg as usize].clone()),
+            ArgGet(arg) => stack.push_arg(args[*arg as usize]),
 
             // Create a `FuncIndex` from the function that we're calling and
             // then run through our other infrastructure to call into this
@@ -1156,8 +1279,11 @@ impl CallAdapter {
                     None => return Err(Trap::new("Missing Memory Export which is necessary")),
                     Some(s) => s,
                 };
-                let val = String::from(pop(stack, ValType::String).unwrap_string());
-                let bytes = val.as_bytes();
+                let val = pop_item(stack, ValType::String);
+                let bytes = match &val {
+                    StackVal::Str(s) => s.as_bytes(),
+                    StackVal::Val(val) => val.unwrap_string().as_bytes(),
+                };
                 stack.push(Val::I32(bytes.len() as i32));
                 self.callcore(&s.malloc, stack)?;
                 let ptr = pop(stack, ValType::I32).unwrap_i32() as usize;
@@ -1185,7 +1311,7 @@ impl CallAdapter {
                 let sigidx = self.instance.module().local.functions[idx];
                 let sig = &self.instance.module().local.signatures[sigidx];
                 let params_start = stack.len() + 2 - sig.params.len();
-                let params: Vec<Val> = stack[params_start..].iter().cloned().collect();
+                let params = stack.to_vals(params_start);
                 finally.push((CallCore(*id), params));
             }
 
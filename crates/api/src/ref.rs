@@ -97,6 +97,16 @@ impl AnyRef {
         }
     }
 
+    /// Returns the address of the data this reference points to, or zero for
+    /// a null reference.
+    pub(crate) fn addr(&self) -> usize {
+        match self {
+            AnyRef::Null => 0,
+            AnyRef::Ref(InternalRef(r)) => &**r as *const dyn InternalRefBase as *const u8 as usize,
+            AnyRef::Other(OtherRef(r)) => &**r as *const RefCell<AnyAndHostInfo> as usize,
+        }
+    }
+
     /// Returns a mutable reference to the host information if available.
     /// # Panics
     /// Panics if `AnyRef` is already borrowed or `AnyRef` is `Null`.
@@ -1,14 +1,20 @@
-use anyhow::Result;
-use std::cell::RefCell;
+use crate::externals::read_ref_bits;
+use crate::{Extern, Instance, Module, Trap, Val};
+use anyhow::{bail, Result};
+use std::any::Any;
+use std::cell::{Cell, RefCell};
+use std::collections::{HashMap, HashSet};
 use std::fmt;
 use std::path::Path;
-use std::rc::Rc;
+use std::rc::{Rc, Weak};
 use std::sync::{Arc, Mutex};
 use wasmparser::{OperatorValidatorConfig, ValidatingParserConfig};
 use wasmtime_environ::settings::{self, Configurable};
 use wasmtime_environ::CacheConfig;
+use wasmtime_environ::Export as EntityExport;
 use wasmtime_jit::{native, CompilationStrategy, Compiler};
 use wasmtime_profiling::{JitDumpAgent, ProfilingAgent, ProfilingStrategy};
+use wasmtime_runtime::{Export, InstanceHandle, MemoryGrowHook, VMGlobalDefinition, VMInterrupts};
 
 // Runtime Environment
 
@@ -28,6 +34,7 @@ pub struct Config {
     pub(crate) strategy: CompilationStrategy,
     pub(crate) cache_config: CacheConfig,
     pub(crate) profiler: Option<Arc<Mutex<Box<dyn ProfilingAgent + Send>>>>,
+    pub(crate) max_wasm_stack: usize,
 }
 
 impl Config {
@@ -68,6 +75,7 @@ impl Config {
             cache_config: CacheConfig::new_cache_disabled(),
             interface_types: false,
             profiler: None,
+            max_wasm_stack: 1 << 20,
         }
     }
 
@@ -297,6 +305,29 @@ impl Config {
         self
     }
 
+    /// Configures the maximum number of bytes of native stack which executing
+    /// WebAssembly code may use.
+    ///
+    /// WebAssembly code executes on the native stack of the thread calling
+    /// into it. The limit is counted from the outermost call into wasm made by
+    /// a [`Store`], and includes the stack used by any host functions called
+    /// from wasm in the meantime. Wasm code which would exceed it, for example
+    /// through unbounded recursion, traps with a
+    /// [`TrapKind::StackOverflow`](crate::TrapKind::StackOverflow) trap.
+    ///
+    /// The limit is only enforced by code compiled with Cranelift for x86
+    /// targets. Code compiled with Lightbeam, or for any other architecture,
+    /// doesn't check it at all. Running out of native stack entirely is still
+    /// reported as a stack overflow trap when it happens in wasm code, so this
+    /// should be configured well below the stack size of the threads running
+    /// wasm.
+    ///
+    /// The default value for this is 1 MiB.
+    pub fn max_wasm_stack(&mut self, size: usize) -> &mut Self {
+        self.max_wasm_stack = size;
+        self
+    }
+
     /// Loads cache configuration specified at `path`.
     ///
     /// This method will read the file specified by `path` on the filesystem and
@@ -358,6 +389,7 @@ impl fmt::Debug for Config {
             .field("wasm_bulk_memory", &features.enable_bulk_memory)
             .field("wasm_simd", &features.enable_simd)
             .field("wasm_multi_value", &features.enable_multi_value)
+            .field("max_wasm_stack", &self.max_wasm_stack)
             .field(
                 "flags",
                 &settings::Flags::new(self.flags.clone()).to_string(),
@@ -481,6 +513,15 @@ pub struct Store {
 struct StoreInner {
     engine: Engine,
     compiler: RefCell<Compiler>,
+    trap_hooks: RefCell<Vec<Rc<dyn Fn(&Trap)>>>,
+    memory_limit: Rc<Cell<Option<usize>>>,
+    memory_usage: Rc<Cell<usize>>,
+    interrupts: Box<VMInterrupts>,
+    live_instances: Rc<Cell<usize>>,
+    live_externs: Rc<Cell<usize>>,
+    live_funcs: Rc<Cell<usize>>,
+    instance_limit: Cell<Option<usize>>,
+    global_refs: RefCell<Weak<GlobalRefs>>,
 }
 
 impl Store {
@@ -492,10 +533,24 @@ impl Store {
             engine.config.strategy,
             engine.config.cache_config.clone(),
         );
+        let interrupts = Box::new(VMInterrupts::new(engine.config.max_wasm_stack));
+        interrupts.set_memory_grow_hook(MemoryGrowHook {
+            reserve: MemoryReservation::reserve_growth,
+            release: MemoryReservation::release_growth,
+        });
         Store {
             inner: Rc::new(StoreInner {
                 engine: engine.clone(),
                 compiler: RefCell::new(compiler),
+                trap_hooks: RefCell::new(Vec::new()),
+                memory_limit: Rc::new(Cell::new(None)),
+                memory_usage: Rc::new(Cell::new(0)),
+                interrupts,
+                live_instances: Rc::new(Cell::new(0)),
+                live_externs: Rc::new(Cell::new(0)),
+                live_funcs: Rc::new(Cell::new(0)),
+                instance_limit: Cell::new(None),
+                global_refs: RefCell::new(Weak::new()),
             }),
         }
     }
@@ -513,6 +568,237 @@ impl Store {
         self.inner.compiler.borrow_mut()
     }
 
+    /// Returns the `VMInterrupts` shared by all instances in this store, which
+    /// holds the stack limit checked by compiled wasm code.
+    pub(crate) fn interrupts(&self) -> *const VMInterrupts {
+        &*self.inner.interrupts
+    }
+
+    /// Returns the number of distinct function signatures registered with this
+    /// store's compiler.
+    ///
+    /// Signatures are shared by all modules compiled and host functions
+    /// created within this store, and are never unregistered, so this can be
+    /// used to observe the growth of the signature table in long-running
+    /// hosts.
+    pub fn registered_signature_count(&self) -> usize {
+        self.compiler().signatures().len()
+    }
+
+    /// Registers a callback which is invoked with every [`Trap`] produced by a
+    /// call into a [`Func`](crate::Func) or [`AdapterFunc`](crate::AdapterFunc)
+    /// belonging to this store, before the trap is returned to the caller.
+    ///
+    /// This is intended for centralized logging and metrics. Hooks are invoked
+    /// in the order they were registered, and can't be unregistered.
+    pub fn on_trap(&self, hook: impl Fn(&Trap) + 'static) {
+        self.inner.trap_hooks.borrow_mut().push(Rc::new(hook));
+    }
+
+    pub(crate) fn report_trap(&self, trap: &Trap) {
+        // Clone the list of hooks so that a hook may itself register more.
+        let hooks = self.inner.trap_hooks.borrow().clone();
+        for hook in hooks {
+            hook(trap);
+        }
+    }
+
+    /// Limits the total number of bytes of linear memory which may be allocated
+    /// by memories in this store, whether created through
+    /// [`Memory::new`](crate::Memory::new) or defined by modules instantiated
+    /// with [`Instance::new`](crate::Instance::new).
+    ///
+    /// Once the limit is reached, creating memories, instantiating modules
+    /// which define memories and growing memories fails. This includes growth
+    /// with [`Memory::grow`](crate::Memory::grow) as well as wasm's own
+    /// `memory.grow` instruction, which then returns -1. Memory is returned to
+    /// the store when a memory is dropped.
+    ///
+    /// Setting a limit below the current [`Store::memory_usage`] doesn't free
+    /// any memory, but prevents any further allocations.
+    pub fn set_memory_limit(&self, max_bytes: usize) {
+        self.inner.memory_limit.set(Some(max_bytes));
+    }
+
+    /// Returns the number of bytes of linear memory currently allocated by
+    /// memories which count towards [`Store::set_memory_limit`], including
+    /// pages added by wasm's `memory.grow` instruction.
+    pub fn memory_usage(&self) -> usize {
+        self.inner.memory_usage.get()
+    }
+
+    /// Checks that `bytes` more bytes of linear memory may be allocated
+    /// without exceeding this store's memory limit.
+    pub(crate) fn check_memory_limit(&self, bytes: usize) -> Result<()> {
+        let limit = match self.inner.memory_limit.get() {
+            Some(limit) => limit,
+            None => return Ok(()),
+        };
+        match self.memory_usage().checked_add(bytes) {
+            Some(total) if total <= limit => Ok(()),
+            _ => bail!(
+                "allocating {} bytes of memory would exceed the store's limit of {} bytes",
+                bytes,
+                limit
+            ),
+        }
+    }
+
+    /// Records `bytes` bytes of linear memory as allocated, returning a
+    /// reservation which releases them again when dropped.
+    pub(crate) fn reserve_memory(&self, bytes: usize) -> Result<MemoryReservation> {
+        self.check_memory_limit(bytes)?;
+        let reservation = MemoryReservation {
+            usage: self.inner.memory_usage.clone(),
+            limit: self.inner.memory_limit.clone(),
+            bytes: Cell::new(0),
+        };
+        reservation.add(bytes);
+        Ok(reservation)
+    }
+
+    /// Collects references held by this store which are no longer reachable.
+    ///
+    /// Wasm code may copy references between reference-typed globals without
+    /// the embedding API noticing, so a reference written to a global is kept
+    /// alive even once the global has been overwritten. This releases every
+    /// such reference which no global of a live instance holds any more.
+    /// References held by tables are already released as soon as their
+    /// element is overwritten, and references held by the host itself are
+    /// never collected.
+    ///
+    /// This may be called at any point between wasm invocations. It does
+    /// nothing while wasm code is running, since the references on the wasm
+    /// stack can't be found.
+    pub fn gc(&self) {
+        if self.inner.interrupts.stack_limit.get() != 0 {
+            return;
+        }
+        let refs = self.inner.global_refs.borrow().upgrade();
+        if let Some(refs) = refs {
+            refs.collect();
+        }
+    }
+
+    /// Returns the number of instances created with
+    /// [`Instance::new`](crate::Instance::new) in this store which are still
+    /// alive.
+    ///
+    /// An instance stays alive as long as the `Instance` itself, any of its
+    /// exports, or any instance importing from it is alive, so this is mostly
+    /// useful to check for leaks in tests.
+    pub fn live_instances(&self) -> usize {
+        self.inner.live_instances.get()
+    }
+
+    /// Returns the number of functions, globals, tables and memories defined
+    /// by the host in this store, for example with
+    /// [`Func::wrap0`](crate::Func::wrap0) or
+    /// [`Memory::new`](crate::Memory::new), which are still alive.
+    pub fn live_externs(&self) -> usize {
+        self.inner.live_externs.get() + self.inner.live_funcs.get()
+    }
+
+    /// Limits the total number of instances and host-defined globals, tables
+    /// and memories which may be alive in this store at once, since each of
+    /// those externs is backed by an instance of its own.
+    ///
+    /// Host-defined functions don't count towards the limit and can always be
+    /// created.
+    ///
+    /// Once the limit is reached, instantiating modules and creating host
+    /// globals, tables and memories fails until some are dropped.
+    ///
+    /// Setting a limit below the current number of live instances doesn't
+    /// drop any of them, but prevents any further instances from being
+    /// created.
+    pub fn set_instance_limit(&self, max: usize) {
+        self.inner.instance_limit.set(Some(max));
+    }
+
+    /// Checks that one more instance may be created without exceeding this
+    /// store's instance limit.
+    fn check_instance_limit(&self) -> Result<()> {
+        let limit = match self.inner.instance_limit.get() {
+            Some(limit) => limit,
+            None => return Ok(()),
+        };
+        if self.live_instances() + self.inner.live_externs.get() >= limit {
+            bail!(
+                "store already holds its limit of {} instances and externs",
+                limit
+            );
+        }
+        Ok(())
+    }
+
+    /// Wraps the host state of an instance created with
+    /// [`Instance::new`](crate::Instance::new), counting it as live until the
+    /// instance is deallocated.
+    ///
+    /// Returns an error if the store's instance limit has been reached.
+    pub(crate) fn instance_state(&self, state: Box<dyn Any>) -> Result<HostState> {
+        self.check_instance_limit()?;
+        Ok(HostState::new(state, &self.inner.live_instances))
+    }
+
+    /// Like [`Store::instance_state`], for the instances backing host-defined
+    /// globals, tables and memories.
+    pub(crate) fn extern_state(&self, state: Box<dyn Any>) -> Result<HostState> {
+        self.check_instance_limit()?;
+        Ok(HostState::new(state, &self.inner.live_externs))
+    }
+
+    /// Like [`Store::extern_state`], for the instances backing host-defined
+    /// functions, which aren't subject to the instance limit.
+    pub(crate) fn func_state(&self, state: Box<dyn Any>) -> HostState {
+        HostState::new(state, &self.inner.live_funcs)
+    }
+
+    /// Returns the references stored in the reference-typed globals of this
+    /// store.
+    ///
+    /// These are shared by, and kept alive by, every instance which defines
+    /// reference-typed globals, see [`HostState::with_global_refs`].
+    pub(crate) fn global_refs(&self) -> Rc<GlobalRefs> {
+        let mut slot = self.inner.global_refs.borrow_mut();
+        if let Some(refs) = slot.upgrade() {
+            return refs;
+        }
+        let refs = Rc::new(GlobalRefs {
+            refs: RefCell::new(HashMap::new()),
+            globals: RefCell::new(HashMap::new()),
+        });
+        *slot = Rc::downgrade(&refs);
+        refs
+    }
+
+    /// Compiles `bytes` into a [`Module`] and instantiates it with `imports`.
+    ///
+    /// This is a shorthand for [`Module::new`] followed by [`Instance::new`],
+    /// so `bytes` may be either the text or binary format of a wasm module.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the module fails to compile, or if instantiation
+    /// fails for any of the reasons listed in [`Instance::new`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use wasmtime::*;
+    /// # fn main() -> anyhow::Result<()> {
+    /// let store = Store::default();
+    /// let instance = store.run("(module (func (export \"f\")))", &[])?;
+    /// assert!(instance.get_export("f").is_some());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn run(&self, bytes: impl AsRef<[u8]>, imports: &[Extern]) -> Result<Instance> {
+        let module = Module::new(self, bytes)?;
+        Instance::new(&module, imports)
+    }
+
     /// Returns whether the stores `a` and `b` refer to the same underlying
     /// `Store`.
     ///
@@ -524,6 +810,232 @@ impl Store {
     }
 }
 
+/// The host state of instances created through a [`Store`], which keeps one of
+/// the store's counts of live instances up to date.
+pub(crate) struct HostState {
+    state: Box<dyn Any>,
+    live: Rc<Cell<usize>>,
+    memory: Option<MemoryReservation>,
+    global_refs: Option<Rc<GlobalRefs>>,
+}
+
+impl HostState {
+    fn new(state: Box<dyn Any>, live: &Rc<Cell<usize>>) -> HostState {
+        live.set(live.get() + 1);
+        HostState {
+            state,
+            live: live.clone(),
+            memory: None,
+            global_refs: None,
+        }
+    }
+
+    /// Keeps the references stored in the store's reference-typed globals
+    /// alive for as long as the instance, which defines such globals itself.
+    ///
+    /// Once the instance is created its globals must be registered with
+    /// [`HostState::register_ref_globals`].
+    pub(crate) fn with_global_refs(mut self, refs: Rc<GlobalRefs>) -> HostState {
+        self.global_refs = Some(refs);
+        self
+    }
+
+    /// Registers the reference-typed globals defined by the instance behind
+    /// `handle`, so that [`Store::gc`] keeps the references they hold alive
+    /// until the instance is dropped.
+    pub(crate) fn register_ref_globals(handle: &InstanceHandle) {
+        let state = match handle.host_state().downcast_ref::<HostState>() {
+            Some(state) => state,
+            None => return,
+        };
+        let refs = match &state.global_refs {
+            Some(refs) => refs,
+            None => return,
+        };
+        let local = &handle.module_ref().local;
+        let definitions = local
+            .globals
+            .iter()
+            .skip(local.num_imported_globals)
+            .filter(|(_, global)| global.ty.is_ref())
+            .map(
+                |(index, _)| match handle.lookup_by_declaration(&EntityExport::Global(index)) {
+                    Export::Global(global) => global.definition as *const VMGlobalDefinition,
+                    _ => unreachable!(),
+                },
+            )
+            .collect();
+        refs.globals.borrow_mut().insert(state.key(), definitions);
+    }
+
+    /// Identifies this state, and so its instance, in `GlobalRefs::globals`.
+    ///
+    /// Host states are boxed by their instance as soon as it's created, so
+    /// their address doesn't change afterwards.
+    fn key(&self) -> usize {
+        self as *const HostState as usize
+    }
+
+    /// Attaches the reservation for the instance's defined memories, which is
+    /// released together with the instance.
+    pub(crate) fn with_memory(mut self, reservation: MemoryReservation) -> HostState {
+        self.memory = Some(reservation);
+        self
+    }
+
+    /// Returns the state the instance behind `handle` was created with.
+    pub(crate) fn of(handle: &InstanceHandle) -> &dyn Any {
+        match handle.host_state().downcast_ref::<HostState>() {
+            Some(host) => &*host.state,
+            None => handle.host_state(),
+        }
+    }
+}
+
+impl Drop for HostState {
+    fn drop(&mut self) {
+        self.live.set(self.live.get() - 1);
+        if let Some(refs) = &self.global_refs {
+            refs.globals.borrow_mut().remove(&self.key());
+        }
+    }
+}
+
+/// The references stored in the reference-typed globals of a [`Store`], keyed
+/// by the raw bits which represent them in the globals' definitions.
+///
+/// Wasm code may copy a reference from one global into another without the
+/// embedding API noticing, so a reference can't be released as soon as the
+/// global it was written to is overwritten. Instead references stay alive
+/// until [`Store::gc`] finds that no global holds them any more, or until no
+/// instance defining reference-typed globals is left.
+pub(crate) struct GlobalRefs {
+    refs: RefCell<HashMap<usize, Val>>,
+    /// The definitions of the reference-typed globals of every live instance,
+    /// keyed by `HostState::key`.
+    globals: RefCell<HashMap<usize, Vec<*const VMGlobalDefinition>>>,
+}
+
+impl GlobalRefs {
+    /// Keeps `val`, a reference about to be written to a global, alive and
+    /// returns the bits which represent it in the global's definition, which
+    /// are zero for null references.
+    pub(crate) fn root(&self, val: &Val) -> usize {
+        let bits = match val {
+            Val::AnyRef(r) => r.addr(),
+            Val::FuncRef(Some(f)) => f.addr(),
+            Val::FuncRef(None) => 0,
+            _ => unreachable!("only references are stored in reference-typed globals"),
+        };
+        if bits != 0 {
+            self.refs
+                .borrow_mut()
+                .entry(bits)
+                .or_insert_with(|| val.clone());
+        }
+        bits
+    }
+
+    /// Returns the reference represented by the non-null `bits` read from a
+    /// global's definition.
+    pub(crate) fn get(&self, bits: usize) -> Val {
+        self.refs
+            .borrow()
+            .get(&bits)
+            .cloned()
+            .expect("references stored in globals are rooted")
+    }
+
+    /// Releases the references which aren't stored in any registered global.
+    fn collect(&self) {
+        loop {
+            let live = self
+                .globals
+                .borrow()
+                .values()
+                .flatten()
+                .map(|&definition| unsafe { read_ref_bits(&*definition) })
+                .collect::<HashSet<_>>();
+            let garbage = {
+                let mut refs = self.refs.borrow_mut();
+                let dead = refs
+                    .keys()
+                    .filter(|bits| !live.contains(bits))
+                    .cloned()
+                    .collect::<Vec<_>>();
+                dead.iter()
+                    .filter_map(|bits| refs.remove(bits))
+                    .collect::<Vec<_>>()
+            };
+            if garbage.is_empty() {
+                break;
+            }
+            // Dropping the references may drop instances as well, whose
+            // globals then no longer keep anything alive, so go around again.
+            // This happens outside of any borrow since instances unregister
+            // their globals when dropped.
+            drop(garbage);
+        }
+    }
+}
+
+/// Linear memory accounted against a [`Store`]'s memory limit, which is
+/// released when this is dropped.
+pub(crate) struct MemoryReservation {
+    usage: Rc<Cell<usize>>,
+    limit: Rc<Cell<Option<usize>>>,
+    bytes: Cell<usize>,
+}
+
+impl MemoryReservation {
+    /// Adds `bytes` more bytes to this reservation, which the caller must have
+    /// checked with [`Store::check_memory_limit`].
+    fn add(&self, bytes: usize) {
+        self.bytes.set(self.bytes.get() + bytes);
+        self.usage.set(self.usage.get() + bytes);
+    }
+
+    /// Adds `bytes` more bytes to the reservation of the instance whose host
+    /// state is `state` before one of its memories grows, returning `false`
+    /// if that would exceed the store's memory limit.
+    ///
+    /// This is the `MemoryGrowHook` of every store, so it also sees memories
+    /// grown by wasm's `memory.grow` instruction.
+    fn reserve_growth(state: &dyn Any, bytes: usize) -> bool {
+        let reservation = match Self::of(state) {
+            Some(reservation) => reservation,
+            None => return true,
+        };
+        if let Some(limit) = reservation.limit.get() {
+            match reservation.usage.get().checked_add(bytes) {
+                Some(total) if total <= limit => {}
+                _ => return false,
+            }
+        }
+        reservation.add(bytes);
+        true
+    }
+
+    /// Returns `bytes` bytes added by `reserve_growth` for a memory which then
+    /// failed to grow.
+    fn release_growth(state: &dyn Any, bytes: usize) {
+        if let Some(reservation) = Self::of(state) {
+            reservation.bytes.set(reservation.bytes.get() - bytes);
+            reservation.usage.set(reservation.usage.get() - bytes);
+        }
+    }
+
+    fn of(state: &dyn Any) -> Option<&MemoryReservation> {
+        state.downcast_ref::<HostState>()?.memory.as_ref()
+    }
+}
+
+impl Drop for MemoryReservation {
+    fn drop(&mut self) {
+        self.usage.set(self.usage.get() - self.bytes.get());
+    }
+}
+
 impl Default for Store {
     fn default() -> Store {
         Store::new(&Engine::default())
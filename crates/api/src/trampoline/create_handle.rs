@@ -1,8 +1,7 @@
 //! Support for a calling of an imported function.
 
-use crate::runtime::Store;
+use crate::runtime::{HostState, Store};
 use anyhow::Result;
-use std::any::Any;
 use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 use wasmtime_environ::entity::PrimaryMap;
@@ -17,7 +16,7 @@ pub(crate) fn create_handle(
     store: &Store,
     finished_functions: PrimaryMap<DefinedFuncIndex, *mut [VMFunctionBody]>,
     trampolines: HashMap<VMSharedSignatureIndex, VMTrampoline>,
-    state: Box<dyn Any>,
+    state: HostState,
 ) -> Result<InstanceHandle> {
     let imports = Imports::new(
         HashSet::new(),
@@ -52,7 +51,8 @@ pub(crate) fn create_handle(
                 .validating_config
                 .operator_config
                 .enable_bulk_memory,
-            state,
+            Box::new(state),
+            store.interrupts(),
         )?)
     }
 }
@@ -1,13 +1,13 @@
 //! Support for a calling of an imported function.
 
 use super::create_handle::create_handle;
-use crate::{Callable, FuncType, Store, Trap, Val};
+use crate::runtime::HostState;
+use crate::{Callable, Caller, FuncType, Store, Trap, Val};
 use anyhow::{bail, Result};
 use std::any::Any;
 use std::cmp;
 use std::collections::HashMap;
 use std::mem;
-use std::panic::{self, AssertUnwindSafe};
 use std::rc::Rc;
 use wasmtime_environ::entity::{EntityRef, PrimaryMap};
 use wasmtime_environ::ir::types;
@@ -27,55 +27,56 @@ use wasmtime_runtime::{InstanceHandle, VMContext, VMFunctionBody, VMTrampoline};
 
 struct TrampolineState {
     func: Rc<dyn Callable + 'static>,
+    store: Store,
     #[allow(dead_code)]
     code_memory: CodeMemory,
 }
 
 impl TrampolineState {
-    fn new(func: Rc<dyn Callable + 'static>, code_memory: CodeMemory) -> Self {
-        TrampolineState { func, code_memory }
+    fn new(func: Rc<dyn Callable + 'static>, store: &Store, code_memory: CodeMemory) -> Self {
+        TrampolineState {
+            func,
+            store: store.clone(),
+            code_memory,
+        }
     }
 }
 
 unsafe extern "C" fn stub_fn(
     vmctx: *mut VMContext,
-    _caller_vmctx: *mut VMContext,
+    caller_vmctx: *mut VMContext,
     call_id: u32,
     values_vec: *mut i128,
 ) {
-    // Here we are careful to use `catch_unwind` to ensure Rust panics don't
-    // unwind past us. The primary reason for this is that Rust considers it UB
-    // to unwind past an `extern "C"` function. Here we are in an `extern "C"`
-    // function and the cross into wasm was through an `extern "C"` function at
-    // the base of the stack as well. We'll need to wait for assorted RFCs and
-    // language features to enable this to be done in a sound and stable fashion
-    // before avoiding catching the panic here.
+    // Here we are careful to use `Trap::catch_panic` to ensure Rust panics
+    // don't unwind past us. The primary reason for this is that Rust considers
+    // it UB to unwind past an `extern "C"` function. Here we are in an
+    // `extern "C"` function and the cross into wasm was through an
+    // `extern "C"` function at the base of the stack as well. We'll need to
+    // wait for assorted RFCs and language features to enable this to be done
+    // in a sound and stable fashion before avoiding catching the panic here.
     //
     // Also note that there are intentionally no local variables on this stack
     // frame. The reason for that is that some of the "raise" functions we have
     // below will trigger a longjmp, which won't run local destructors if we
     // have any. To prevent leaks we avoid having any local destructors by
     // avoiding local variables.
-    let result = panic::catch_unwind(AssertUnwindSafe(|| call_stub(vmctx, call_id, values_vec)));
+    let result = Trap::catch_panic(|| call_stub(vmctx, caller_vmctx, call_id, values_vec));
 
     match result {
         Ok(Ok(())) => {}
 
-        // If a trap was raised (an error returned from the imported function)
-        // then we smuggle the trap through `Box<dyn Error>` through to the
-        // call-site, which gets unwrapped in `Trap::from_jit` later on as we
-        // convert from the internal `Trap` type to our own `Trap` type in this
-        // crate.
-        Ok(Err(trap)) => wasmtime_runtime::raise_user_trap(Box::new(trap)),
-
-        // And finally if the imported function panicked, then we trigger the
-        // form of unwinding that's safe to jump over wasm code on all
-        // platforms.
-        Err(panic) => wasmtime_runtime::resume_panic(panic),
+        // If a trap was raised (an error returned from the imported function,
+        // or a panic in it) then we smuggle the trap through `Box<dyn Error>`
+        // through to the call-site, which gets unwrapped in `Trap::from_jit`
+        // later on as we convert from the internal `Trap` type to our own
+        // `Trap` type in this crate.
+        Ok(Err(trap)) | Err(trap) => wasmtime_runtime::raise_user_trap(Box::new(trap)),
     }
 
     unsafe fn call_stub(
         vmctx: *mut VMContext,
+        caller_vmctx: *mut VMContext,
         call_id: u32,
         values_vec: *mut i128,
     ) -> Result<(), Trap> {
@@ -97,11 +98,11 @@ unsafe extern "C" fn stub_fn(
         };
 
         let mut returns = vec![Val::null(); returns_len];
-        let state = &instance
-            .host_state()
+        let state = &HostState::of(&instance)
             .downcast_ref::<TrampolineState>()
             .expect("state");
-        state.func.call(&args, &mut returns)?;
+        let caller = Caller::new(&state.store, caller_vmctx);
+        state.func.call_with_caller(caller, &args, &mut returns)?;
 
         let module = instance.module_ref();
         let signature =
@@ -304,13 +305,13 @@ pub fn create_handle_with_function(
     // code memory (makes it executable) and ensuring all our various bits of
     // state make it into the instance constructors.
     code_memory.publish();
-    let trampoline_state = TrampolineState::new(func.clone(), code_memory);
+    let trampoline_state = TrampolineState::new(func.clone(), store, code_memory);
     create_handle(
         module,
         store,
         finished_functions,
         trampolines,
-        Box::new(trampoline_state),
+        store.func_state(Box::new(trampoline_state)),
     )
 }
 
@@ -346,5 +347,6 @@ pub unsafe fn create_handle_with_raw_function(
     let sig_id = store.compiler().signatures().register(&sig);
     trampolines.insert(sig_id, trampoline);
 
+    let state = store.func_state(state);
     create_handle(module, store, finished_functions, trampolines, state)
 }
@@ -1,16 +1,30 @@
 use super::create_handle::create_handle;
-use crate::Store;
-use crate::{GlobalType, Mutability, Val};
+use crate::runtime::{HostState, Store};
+use crate::{GlobalType, Mutability, Val, ValType};
 use anyhow::{bail, Result};
 use wasmtime_environ::entity::PrimaryMap;
-use wasmtime_environ::{wasm, Module};
+use wasmtime_environ::{ir, wasm, Module};
 use wasmtime_runtime::InstanceHandle;
 
 pub fn create_global(store: &Store, gt: &GlobalType, val: Val) -> Result<InstanceHandle> {
     let global = wasm::Global {
-        ty: match gt.content().get_wasmtime_type() {
-            Some(t) => t,
-            None => bail!("cannot support {:?} as a wasm global type", gt.content()),
+        ty: match gt.content() {
+            // Reference-typed globals are stored as a pointer-sized value
+            // identifying the reference, which the store keeps alive.
+            ValType::AnyRef | ValType::FuncRef => {
+                match store.compiler().frontend_config().pointer_type() {
+                    ir::types::I32 => ir::types::R32,
+                    _ => ir::types::R64,
+                }
+            }
+            // Interface-types strings can't be used by core wasm at all, so
+            // the embedding API keeps their contents and the wasm global is
+            // only a placeholder.
+            ValType::String => ir::types::I64,
+            content => match content.get_wasmtime_type() {
+                Some(t) => t,
+                None => bail!("cannot support {:?} as a wasm global type", content),
+            },
         },
         mutability: match gt.mutability() {
             Mutability::Const => false,
@@ -21,6 +35,9 @@ pub fn create_global(store: &Store, gt: &GlobalType, val: Val) -> Result<Instanc
             Val::I64(i) => wasm::GlobalInit::I64Const(i),
             Val::F32(f) => wasm::GlobalInit::F32Const(f),
             Val::F64(f) => wasm::GlobalInit::F64Const(f),
+            Val::V128(v) => wasm::GlobalInit::V128Const(ir::immediates::V128Imm(v.to_le_bytes())),
+            Val::AnyRef(_) | Val::FuncRef(_) => wasm::GlobalInit::RefNullConst,
+            Val::String(_) => wasm::GlobalInit::I64Const(0),
             _ => unimplemented!("create_global for {:?}", gt),
         },
     };
@@ -30,12 +47,11 @@ pub fn create_global(store: &Store, gt: &GlobalType, val: Val) -> Result<Instanc
         "global".to_string(),
         wasmtime_environ::Export::Global(global_id),
     );
-    let handle = create_handle(
-        module,
-        store,
-        PrimaryMap::new(),
-        Default::default(),
-        Box::new(()),
-    )?;
+    let mut state = store.extern_state(Box::new(()))?;
+    if gt.content().is_ref() {
+        state = state.with_global_refs(store.global_refs());
+    }
+    let handle = create_handle(module, store, PrimaryMap::new(), Default::default(), state)?;
+    HostState::register_ref_globals(&handle);
     Ok(handle)
 }
@@ -3,10 +3,16 @@ use crate::MemoryType;
 use crate::Store;
 use anyhow::Result;
 use wasmtime_environ::entity::PrimaryMap;
-use wasmtime_environ::{wasm, Module};
+use wasmtime_environ::{wasm, Module, WASM_PAGE_SIZE};
 use wasmtime_runtime::InstanceHandle;
 
 pub fn create_handle_with_memory(store: &Store, memory: &MemoryType) -> Result<InstanceHandle> {
+    // Account for the memory's initial size against the store's limit. The
+    // reservation is kept with the instance's host state so that growth can be
+    // accounted for too, and so the memory is released when it's dropped.
+    let initial_bytes = memory.limits().min() as usize * WASM_PAGE_SIZE as usize;
+    let reservation = store.reserve_memory(initial_bytes)?;
+
     let mut module = Module::new();
 
     let memory = wasm::Memory {
@@ -28,6 +34,6 @@ pub fn create_handle_with_memory(store: &Store, memory: &MemoryType) -> Result<I
         store,
         PrimaryMap::new(),
         Default::default(),
-        Box::new(()),
+        store.extern_state(Box::new(()))?.with_memory(reservation),
     )
 }
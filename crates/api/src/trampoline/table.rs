@@ -1,9 +1,9 @@
 use super::create_handle::create_handle;
 use crate::Store;
 use crate::{TableType, ValType};
-use anyhow::{bail, Result};
+use anyhow::Result;
 use wasmtime_environ::entity::PrimaryMap;
-use wasmtime_environ::{wasm, Module};
+use wasmtime_environ::{ir, wasm, Module};
 use wasmtime_runtime::InstanceHandle;
 
 pub fn create_handle_with_table(store: &Store, table: &TableType) -> Result<InstanceHandle> {
@@ -14,7 +14,15 @@ pub fn create_handle_with_table(store: &Store, table: &TableType) -> Result<Inst
         maximum: table.limits().max(),
         ty: match table.element() {
             ValType::FuncRef => wasm::TableElementType::Func,
-            _ => bail!("cannot support {:?} as a table element", table.element()),
+            // Elements of `anyref` tables are pointer-sized references.
+            ValType::AnyRef if cfg!(target_pointer_width = "32") => {
+                wasm::TableElementType::Val(ir::types::R32)
+            }
+            ValType::AnyRef => wasm::TableElementType::Val(ir::types::R64),
+            // Tables of plain values can't be used by core wasm at all, so the
+            // embedding API keeps their contents and the wasm table only
+            // tracks their size.
+            _ => wasm::TableElementType::Val(ir::types::I64),
         },
     };
     let tunable = Default::default();
@@ -31,6 +39,6 @@ pub fn create_handle_with_table(store: &Store, table: &TableType) -> Result<Inst
         store,
         PrimaryMap::new(),
         Default::default(),
-        Box::new(()),
+        store.extern_state(Box::new(()))?,
     )
 }
@@ -1,8 +1,11 @@
 use crate::frame_info::FRAME_INFO;
 use crate::FrameInfo;
 use backtrace::Backtrace;
+use std::any::Any;
 use std::fmt;
+use std::panic::{self, AssertUnwindSafe};
 use std::sync::Arc;
+use wasmtime_environ::ir::TrapCode;
 
 /// A struct representing an aborted instruction execution, with a message
 /// indicating the cause.
@@ -11,8 +14,50 @@ pub struct Trap {
     inner: Arc<TrapInner>,
 }
 
+/// The general cause of a [`Trap`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum TrapKind {
+    /// An out-of-bounds access to a linear memory.
+    MemoryOutOfBounds,
+    /// An out-of-bounds access to a table, or a call through an uninitialized
+    /// or mismatched table element.
+    TableOutOfBounds,
+    /// An integer division or remainder by zero.
+    IntegerDivisionByZero,
+    /// An integer overflow, including invalid float-to-integer conversions.
+    IntegerOverflow,
+    /// An `unreachable` instruction was executed.
+    Unreachable,
+    /// The call stack was exhausted.
+    StackOverflow,
+    /// The trap was created by host code, for example returned from a
+    /// [`Callable`](crate::Callable) or constructed with [`Trap::new`].
+    HostError,
+    /// Any other trap raised by wasm code.
+    Other,
+}
+
+impl TrapKind {
+    fn from_trap_code(code: TrapCode) -> Self {
+        match code {
+            TrapCode::HeapOutOfBounds | TrapCode::OutOfBounds => TrapKind::MemoryOutOfBounds,
+            TrapCode::TableOutOfBounds | TrapCode::IndirectCallToNull | TrapCode::BadSignature => {
+                TrapKind::TableOutOfBounds
+            }
+            TrapCode::IntegerDivisionByZero => TrapKind::IntegerDivisionByZero,
+            TrapCode::IntegerOverflow | TrapCode::BadConversionToInteger => {
+                TrapKind::IntegerOverflow
+            }
+            TrapCode::UnreachableCodeReached => TrapKind::Unreachable,
+            TrapCode::StackOverflow => TrapKind::StackOverflow,
+            TrapCode::Interrupt | TrapCode::User(_) => TrapKind::Other,
+        }
+    }
+}
+
 struct TrapInner {
     message: String,
+    kind: TrapKind,
     wasm_trace: Vec<FrameInfo>,
     native_trace: Backtrace,
 }
@@ -29,7 +74,11 @@ impl Trap {
     /// assert_eq!("unexpected error", trap.message());
     /// ```
     pub fn new<I: Into<String>>(message: I) -> Self {
-        Trap::new_with_trace(message.into(), Backtrace::new_unresolved())
+        Trap::new_with_trace(
+            message.into(),
+            TrapKind::HostError,
+            Backtrace::new_unresolved(),
+        )
     }
 
     pub(crate) fn from_jit(jit: wasmtime_runtime::Trap) -> Self {
@@ -48,12 +97,37 @@ impl Trap {
                     .expect("only `Trap` user errors are supported")
             }
             wasmtime_runtime::Trap::Wasm { desc, backtrace } => {
-                Trap::new_with_trace(desc.to_string(), backtrace)
+                let kind = TrapKind::from_trap_code(desc.trap_code);
+                Trap::new_with_trace(desc.to_string(), kind, backtrace)
             }
         }
     }
 
-    fn new_with_trace(message: String, native_trace: Backtrace) -> Self {
+    /// Runs `f`, a call into a host function, turning a panic into a `Trap`
+    /// carrying the panic's message.
+    ///
+    /// This is the one place where host functions' panics are caught, however
+    /// the host function was created and whether it's called from wasm or
+    /// directly from the host. A panic must not unwind through wasm frames,
+    /// so this has to run before control returns to wasm.
+    pub(crate) fn catch_panic<T>(f: impl FnOnce() -> T) -> Result<T, Trap> {
+        panic::catch_unwind(AssertUnwindSafe(f)).map_err(Trap::from_panic)
+    }
+
+    /// Creates a `Trap` describing a panic in a host function, given the
+    /// panic's payload as caught by `std::panic::catch_unwind`.
+    fn from_panic(payload: Box<dyn Any + Send>) -> Self {
+        let message = if let Some(s) = payload.downcast_ref::<&str>() {
+            s
+        } else if let Some(s) = payload.downcast_ref::<String>() {
+            s.as_str()
+        } else {
+            "Box<Any>"
+        };
+        Trap::new(format!("host function panicked: {}", message))
+    }
+
+    fn new_with_trace(message: String, kind: TrapKind, native_trace: Backtrace) -> Self {
         let mut wasm_trace = Vec::new();
         for frame in native_trace.frames() {
             let pc = frame.ip() as usize;
@@ -64,6 +138,7 @@ impl Trap {
         Trap {
             inner: Arc::new(TrapInner {
                 message,
+                kind,
                 wasm_trace,
                 native_trace,
             }),
@@ -75,6 +150,11 @@ impl Trap {
         &self.inner.message
     }
 
+    /// Returns the general cause of this trap.
+    pub fn kind(&self) -> TrapKind {
+        self.inner.kind
+    }
+
     /// Returns a list of function frames in WebAssembly code that led to this
     /// trap happening.
     pub fn trace(&self) -> &[FrameInfo] {
@@ -86,6 +166,7 @@ impl fmt::Debug for Trap {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("Trap")
             .field("message", &self.inner.message)
+            .field("kind", &self.inner.kind)
             .field("wasm_trace", &self.inner.wasm_trace)
             .field("native_trace", &self.inner.native_trace)
             .finish()
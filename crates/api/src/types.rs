@@ -1,3 +1,4 @@
+use std::fmt;
 use wasmtime_environ::{ir, wasm};
 
 // Type Representations
@@ -5,7 +6,7 @@ use wasmtime_environ::{ir, wasm};
 // Type attributes
 
 /// Indicator of whether a global is mutable or not
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Mutability {
     /// The global is constant and its value does not change
     Const,
@@ -17,7 +18,7 @@ pub enum Mutability {
 /// table/memory types.
 ///
 /// A minimum is always available but the maximum may not be present.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Limits {
     min: u32,
     max: Option<u32>,
@@ -48,7 +49,7 @@ impl Limits {
 // Value Types
 
 /// A list of all possible value types in WebAssembly.
-#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
 pub enum ValType {
     /// Signed 32 bit integer.
     I32,
@@ -86,6 +87,30 @@ pub enum ValType {
     U64,
 }
 
+impl fmt::Display for ValType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ValType::I32 => "i32",
+            ValType::I64 => "i64",
+            ValType::F32 => "f32",
+            ValType::F64 => "f64",
+            ValType::V128 => "v128",
+            ValType::AnyRef => "anyref",
+            ValType::FuncRef => "funcref",
+            ValType::String => "string",
+            ValType::S8 => "s8",
+            ValType::S16 => "s16",
+            ValType::S32 => "s32",
+            ValType::S64 => "s64",
+            ValType::U8 => "u8",
+            ValType::U16 => "u16",
+            ValType::U32 => "u32",
+            ValType::U64 => "u64",
+        };
+        f.write_str(name)
+    }
+}
+
 impl ValType {
     /// Returns true if `ValType` matches any of the numeric types. (e.g. `I32`,
     /// `I64`, `F32`, `F64`).
@@ -115,6 +140,22 @@ impl ValType {
         }
     }
 
+    /// Returns the number of bytes a value of this type occupies when stored
+    /// in linear memory.
+    ///
+    /// Returns `None` for types without a fixed-size representation in memory,
+    /// namely references and strings.
+    pub fn size_bytes(&self) -> Option<usize> {
+        match self {
+            ValType::S8 | ValType::U8 => Some(1),
+            ValType::S16 | ValType::U16 => Some(2),
+            ValType::I32 | ValType::F32 | ValType::S32 | ValType::U32 => Some(4),
+            ValType::I64 | ValType::F64 | ValType::S64 | ValType::U64 => Some(8),
+            ValType::V128 => Some(16),
+            ValType::AnyRef | ValType::FuncRef | ValType::String => None,
+        }
+    }
+
     pub(crate) fn get_wasmtime_type(&self) -> Option<ir::Type> {
         match self {
             ValType::I32 => Some(ir::types::I32),
@@ -153,7 +194,7 @@ impl ValType {
 ///
 /// This list can be found in [`ImportType`] or [`ExportType`], so these types
 /// can either be imported or exported.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum ExternType {
     /// This external type is the type of a WebAssembly function.
     Func(FuncType),
@@ -201,6 +242,73 @@ impl ExternType {
     }
 }
 
+/// Formats an `ExternType` the way it's described in error messages, for
+/// example `func (i32) -> i32` or `memory {min 1, max 3}`.
+impl fmt::Display for ExternType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExternType::Func(ty) => {
+                f.write_str("func ")?;
+                fmt_signature(f, ty.params(), ty.results())
+            }
+            ExternType::Global(ty) => {
+                f.write_str("global ")?;
+                if ty.mutability() == Mutability::Var {
+                    f.write_str("mut ")?;
+                }
+                write!(f, "{}", ty.content())
+            }
+            ExternType::Table(ty) => {
+                write!(f, "table {} ", ty.element())?;
+                fmt_limits(f, ty.limits())
+            }
+            ExternType::Memory(ty) => {
+                f.write_str("memory ")?;
+                fmt_limits(f, ty.limits())
+            }
+            ExternType::Adapter(ty) => {
+                f.write_str("adapter ")?;
+                fmt_signature(f, ty.params(), ty.results())
+            }
+        }
+    }
+}
+
+fn fmt_signature(
+    f: &mut fmt::Formatter<'_>,
+    params: &[ValType],
+    results: &[ValType],
+) -> fmt::Result {
+    fn fmt_list(f: &mut fmt::Formatter<'_>, tys: &[ValType]) -> fmt::Result {
+        f.write_str("(")?;
+        for (i, ty) in tys.iter().enumerate() {
+            if i > 0 {
+                f.write_str(", ")?;
+            }
+            write!(f, "{}", ty)?;
+        }
+        f.write_str(")")
+    }
+
+    fmt_list(f, params)?;
+    match results {
+        [] => Ok(()),
+        [result] => write!(f, " -> {}", result),
+        results => {
+            f.write_str(" -> ")?;
+            fmt_list(f, results)
+        }
+    }
+}
+
+fn fmt_limits(f: &mut fmt::Formatter<'_>, limits: &Limits) -> fmt::Result {
+    write!(f, "{{min {}", limits.min())?;
+    if let Some(max) = limits.max() {
+        write!(f, ", max {}", max)?;
+    }
+    f.write_str("}")
+}
+
 // Function Types
 fn from_wasmtime_abiparam(param: &ir::AbiParam) -> Option<ValType> {
     assert_eq!(param.purpose, ir::ArgumentPurpose::Normal);
@@ -239,7 +347,7 @@ impl AdapterType {
 /// A descriptor for a function in a WebAssembly module.
 ///
 /// WebAssembly functions can have 0 or more parameters and results.
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct FuncType {
     params: Box<[ValType]>,
     results: Box<[ValType]>,
@@ -254,6 +362,43 @@ impl FuncType {
         FuncType { params, results }
     }
 
+    /// Creates a new function descriptor from vectors of parameters and
+    /// results.
+    ///
+    /// This is the same as [`FuncType::new`], but avoids having to convert
+    /// each `Vec` into a boxed slice first.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use wasmtime::*;
+    /// let ty = FuncType::from_vecs(vec![ValType::I32], vec![]);
+    /// assert_eq!(ty.params(), &[ValType::I32]);
+    /// assert!(ty.results().is_empty());
+    /// ```
+    pub fn from_vecs(params: Vec<ValType>, results: Vec<ValType>) -> FuncType {
+        FuncType::new(params.into_boxed_slice(), results.into_boxed_slice())
+    }
+
+    /// Returns a [`FuncTypeBuilder`] for incrementally building a function
+    /// descriptor, one parameter and result at a time.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use wasmtime::*;
+    /// let ty = FuncType::builder()
+    ///     .param(ValType::I32)
+    ///     .param(ValType::I64)
+    ///     .result(ValType::F32)
+    ///     .build();
+    /// assert_eq!(ty.params(), &[ValType::I32, ValType::I64]);
+    /// assert_eq!(ty.results(), &[ValType::F32]);
+    /// ```
+    pub fn builder() -> FuncTypeBuilder {
+        FuncTypeBuilder::default()
+    }
+
     /// Returns the list of parameter types for this function.
     pub fn params(&self) -> &[ValType] {
         &self.params
@@ -313,6 +458,32 @@ impl FuncType {
     }
 }
 
+/// A builder for [`FuncType`], created with [`FuncType::builder`].
+#[derive(Debug, Clone, Default)]
+pub struct FuncTypeBuilder {
+    params: Vec<ValType>,
+    results: Vec<ValType>,
+}
+
+impl FuncTypeBuilder {
+    /// Appends a parameter of type `ty` to the function descriptor.
+    pub fn param(mut self, ty: ValType) -> FuncTypeBuilder {
+        self.params.push(ty);
+        self
+    }
+
+    /// Appends a result of type `ty` to the function descriptor.
+    pub fn result(mut self, ty: ValType) -> FuncTypeBuilder {
+        self.results.push(ty);
+        self
+    }
+
+    /// Creates the [`FuncType`] with the parameters and results added so far.
+    pub fn build(self) -> FuncType {
+        FuncType::from_vecs(self.params, self.results)
+    }
+}
+
 // Global Types
 
 /// A WebAssembly global descriptor.
@@ -320,7 +491,7 @@ impl FuncType {
 /// This type describes an instance of a global in a WebAssembly module. Globals
 /// are local to an [`Instance`](crate::Instance) and are either immutable or
 /// mutable.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct GlobalType {
     content: ValType,
     mutability: Mutability,
@@ -349,7 +520,14 @@ impl GlobalType {
     /// Returns `None` if the wasmtime global has a type that we can't
     /// represent, but that should only very rarely happen and indicate a bug.
     pub(crate) fn from_wasmtime_global(global: &wasm::Global) -> Option<GlobalType> {
-        let ty = ValType::from_wasmtime_type(global.ty)?;
+        // Wasm globals don't record which kind of reference they hold, so
+        // reference-typed ones are reported as `anyref`, which every reference
+        // is a subtype of, unless the declared type is known.
+        let ty = if global.ty.is_ref() {
+            ValType::AnyRef
+        } else {
+            ValType::from_wasmtime_type(global.ty)?
+        };
         let mutability = if global.mutability {
             Mutability::Var
         } else {
@@ -366,7 +544,7 @@ impl GlobalType {
 /// Tables are contiguous chunks of a specific element, typically a `funcref` or
 /// an `anyref`. The most common use for tables is a function table through
 /// which `call_indirect` can invoke other functions.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct TableType {
     element: ValType,
     limits: Limits,
@@ -390,12 +568,10 @@ impl TableType {
     }
 
     pub(crate) fn from_wasmtime_table(table: &wasm::Table) -> TableType {
-        assert!(if let wasm::TableElementType::Func = table.ty {
-            true
-        } else {
-            false
-        });
-        let ty = ValType::FuncRef;
+        let ty = match table.ty {
+            wasm::TableElementType::Func => ValType::FuncRef,
+            wasm::TableElementType::Val(_) => ValType::AnyRef,
+        };
         let limits = Limits::new(table.minimum, table.maximum);
         TableType::new(ty, limits)
     }
@@ -407,7 +583,7 @@ impl TableType {
 ///
 /// Memories are described in units of pages (64KB) and represent contiguous
 /// chunks of addressable memory.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct MemoryType {
     limits: Limits,
 }
@@ -1,6 +1,8 @@
 use crate::r#ref::AnyRef;
 use crate::{Func, Store, ValType};
 use anyhow::{bail, Result};
+use std::convert::TryFrom;
+use std::fmt;
 use std::ptr;
 use wasmtime_environ::ir;
 
@@ -31,8 +33,9 @@ pub enum Val {
     /// Note that this is a nullable value as well.
     AnyRef(AnyRef),
 
-    /// A first-class reference to a WebAssembly function.
-    FuncRef(Func),
+    /// A first-class reference to a WebAssembly function, or `None` for a null
+    /// `funcref`.
+    FuncRef(Option<Func>),
 
     /// A 128-bit number
     V128(u128),
@@ -95,6 +98,85 @@ impl Val {
         Val::AnyRef(AnyRef::null())
     }
 
+    /// Returns a null `anyref` value.
+    ///
+    /// This is the same as [`Val::null`], but makes the intended reference
+    /// type explicit at the call site.
+    pub fn null_anyref() -> Val {
+        Val::null()
+    }
+
+    /// Returns a null `funcref` value.
+    pub fn null_funcref() -> Val {
+        Val::FuncRef(None)
+    }
+
+    /// Returns the default value for the type `ty`: zero for numbers, a null
+    /// reference for reference types, and an empty string for strings.
+    pub fn default_for(ty: &ValType) -> Val {
+        match ty {
+            ValType::I32 => Val::I32(0),
+            ValType::I64 => Val::I64(0),
+            ValType::F32 => Val::F32(0),
+            ValType::F64 => Val::F64(0),
+            ValType::V128 => Val::V128(0),
+            ValType::AnyRef => Val::null_anyref(),
+            ValType::FuncRef => Val::null_funcref(),
+            ValType::String => Val::String(String::new()),
+            ValType::S8 => Val::S8(0),
+            ValType::S16 => Val::S16(0),
+            ValType::S32 => Val::S32(0),
+            ValType::S64 => Val::S64(0),
+            ValType::U8 => Val::U8(0),
+            ValType::U16 => Val::U16(0),
+            ValType::U32 => Val::U32(0),
+            ValType::U64 => Val::U64(0),
+        }
+    }
+
+    /// Returns `true` if this is a null reference value.
+    ///
+    /// This returns `false` for all non-reference values.
+    pub fn is_null(&self) -> bool {
+        match self {
+            Val::AnyRef(AnyRef::Null) | Val::FuncRef(None) => true,
+            _ => false,
+        }
+    }
+
+    /// Returns `true` if `self` and `other` are the same value, bit for bit.
+    ///
+    /// Unlike comparing the results of [`Val::unwrap_f32`] or
+    /// [`Val::unwrap_f64`], floats are compared by their raw bit patterns, so
+    /// two NaNs with identical bits are equal while `0.0` and `-0.0` are not.
+    /// References are equal if they refer to the same object. Values of
+    /// different types are never equal.
+    pub fn bit_eq(&self, other: &Val) -> bool {
+        match (self, other) {
+            (Val::I32(a), Val::I32(b)) => a == b,
+            (Val::I64(a), Val::I64(b)) => a == b,
+            (Val::F32(a), Val::F32(b)) => a == b,
+            (Val::F64(a), Val::F64(b)) => a == b,
+            (Val::V128(a), Val::V128(b)) => a == b,
+            (Val::AnyRef(a), Val::AnyRef(b)) => a.ptr_eq(b),
+            (Val::FuncRef(None), Val::FuncRef(None)) => true,
+            (Val::FuncRef(Some(a)), Val::FuncRef(Some(b))) => {
+                let (a, b) = (a.wasmtime_function(), b.wasmtime_function());
+                a.address == b.address && a.vmctx == b.vmctx
+            }
+            (Val::S8(a), Val::S8(b)) => a == b,
+            (Val::S16(a), Val::S16(b)) => a == b,
+            (Val::S32(a), Val::S32(b)) => a == b,
+            (Val::S64(a), Val::S64(b)) => a == b,
+            (Val::U8(a), Val::U8(b)) => a == b,
+            (Val::U16(a), Val::U16(b)) => a == b,
+            (Val::U32(a), Val::U32(b)) => a == b,
+            (Val::U64(a), Val::U64(b)) => a == b,
+            (Val::String(a), Val::String(b)) => a == b,
+            _ => false,
+        }
+    }
+
     /// Returns the corresponding [`ValType`] for this `Val`.
     pub fn ty(&self) -> ValType {
         match self {
@@ -145,7 +227,6 @@ impl Val {
         (I64(i64) i64 unwrap_i64 *e)
         (F32(f32) f32 unwrap_f32 f32::from_bits(*e))
         (F64(f64) f64 unwrap_f64 f64::from_bits(*e))
-        (FuncRef(&Func) funcref unwrap_funcref e)
         (V128(u128) v128 unwrap_v128 *e)
 
         (S8(i8) s8 unwrap_s8 *e)
@@ -160,10 +241,107 @@ impl Val {
         (String(&str) string unwrap_string e)
     }
 
+    /// Creates a `v128` value from its 16 bytes, in little-endian order as
+    /// they'd be laid out in linear memory.
+    pub fn v128_from_bytes(bytes: [u8; 16]) -> Val {
+        Val::V128(u128::from_le_bytes(bytes))
+    }
+
+    /// Creates a `v128` value from four 32-bit lanes, where lane 0 holds the
+    /// least significant bits.
+    pub fn v128_from_lanes_u32(lanes: [u32; 4]) -> Val {
+        let v = lanes
+            .iter()
+            .rev()
+            .fold(0, |v, lane| (v << 32) | u128::from(*lane));
+        Val::V128(v)
+    }
+
+    /// Creates a `v128` value from four `f32` lanes, where lane 0 holds the
+    /// least significant bits.
+    pub fn v128_from_lanes_f32(lanes: [f32; 4]) -> Val {
+        Val::v128_from_lanes_u32([
+            lanes[0].to_bits(),
+            lanes[1].to_bits(),
+            lanes[2].to_bits(),
+            lanes[3].to_bits(),
+        ])
+    }
+
+    /// Returns the 16 bytes of this `v128` value, in little-endian order as
+    /// they'd be laid out in linear memory.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `self` is not a `v128`.
+    pub fn v128_bytes(&self) -> [u8; 16] {
+        self.unwrap_v128().to_le_bytes()
+    }
+
+    /// Returns this `v128` value split into four 32-bit lanes, where lane 0
+    /// holds the least significant bits.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `self` is not a `v128`.
+    pub fn v128_lanes_u32(&self) -> [u32; 4] {
+        let v = self.unwrap_v128();
+        [
+            v as u32,
+            (v >> 32) as u32,
+            (v >> 64) as u32,
+            (v >> 96) as u32,
+        ]
+    }
+
+    /// Returns this `v128` value split into two 64-bit lanes, where lane 0
+    /// holds the least significant bits.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `self` is not a `v128`.
+    pub fn v128_lanes_u64(&self) -> [u64; 2] {
+        let v = self.unwrap_v128();
+        [v as u64, (v >> 64) as u64]
+    }
+
+    /// Returns this `v128` value split into four `f32` lanes, where lane 0
+    /// holds the least significant bits.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `self` is not a `v128`.
+    pub fn v128_lanes_f32(&self) -> [f32; 4] {
+        let lanes = self.v128_lanes_u32();
+        [
+            f32::from_bits(lanes[0]),
+            f32::from_bits(lanes[1]),
+            f32::from_bits(lanes[2]),
+            f32::from_bits(lanes[3]),
+        ]
+    }
+
+    /// Attempt to access the underlying function of this `Val`, returning
+    /// `None` if it is not a `funcref` or if it's a null `funcref`.
+    pub fn funcref(&self) -> Option<&Func> {
+        match self {
+            Val::FuncRef(f) => f.as_ref(),
+            _ => None,
+        }
+    }
+
+    /// Returns the underlying function of this `Val`, panicking if it's the
+    /// wrong type.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `self` is not a non-null `funcref`.
+    pub fn unwrap_funcref(&self) -> &Func {
+        self.funcref().expect("expected &Func")
+    }
+
     /// Attempt to access the underlying value of this `Val`, returning
     /// `None` if it is not the correct type.
-    ///
-    /// This will return `Some` for both the `AnyRef` and `FuncRef` types.
     pub fn anyref(&self) -> Option<AnyRef> {
         match self {
             Val::AnyRef(e) => Some(e.clone()),
@@ -183,7 +361,8 @@ impl Val {
 
     pub(crate) fn comes_from_same_store(&self, store: &Store) -> bool {
         match self {
-            Val::FuncRef(f) => Store::same(store, f.store()),
+            Val::FuncRef(Some(f)) => Store::same(store, f.store()),
+            Val::FuncRef(None) => true,
 
             // TODO: need to implement this once we actually finalize what
             // `anyref` will look like and it's actually implemented to pass it
@@ -211,6 +390,34 @@ impl Val {
     }
 }
 
+impl fmt::Display for Val {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Val::I32(i) => write!(f, "{}", i),
+            Val::I64(i) => write!(f, "{}", i),
+            Val::F32(bits) => write!(f, "{}", f32::from_bits(*bits)),
+            Val::F64(bits) => write!(f, "{}", f64::from_bits(*bits)),
+            Val::V128(v) => write!(f, "{:#034x}", v),
+            Val::AnyRef(AnyRef::Null) => write!(f, "anyref(null)"),
+            Val::AnyRef(r) => write!(f, "anyref({:#x})", r.addr()),
+            Val::FuncRef(None) => write!(f, "funcref(null)"),
+            Val::FuncRef(Some(func)) => match func.native_address() {
+                Some(addr) => write!(f, "funcref({:#x})", addr),
+                None => write!(f, "funcref(host)"),
+            },
+            Val::S8(i) => write!(f, "{}", i),
+            Val::S16(i) => write!(f, "{}", i),
+            Val::S32(i) => write!(f, "{}", i),
+            Val::S64(i) => write!(f, "{}", i),
+            Val::U8(i) => write!(f, "{}", i),
+            Val::U16(i) => write!(f, "{}", i),
+            Val::U32(i) => write!(f, "{}", i),
+            Val::U64(i) => write!(f, "{}", i),
+            Val::String(s) => write!(f, "{:?}", s),
+        }
+    }
+}
+
 impl From<i8> for Val {
     fn from(val: i8) -> Val {
         Val::S8(val)
@@ -291,7 +498,92 @@ impl From<AnyRef> for Val {
 
 impl From<Func> for Val {
     fn from(val: Func) -> Val {
-        Val::FuncRef(val)
+        Val::FuncRef(Some(val))
+    }
+}
+
+macro_rules! try_from_val {
+    ($($ty:ty => $variant:ident($bind:ident) $cvt:expr,)*) => ($(
+        impl TryFrom<Val> for $ty {
+            type Error = anyhow::Error;
+
+            fn try_from(val: Val) -> Result<$ty> {
+                match val {
+                    Val::$variant($bind) => Ok($cvt),
+                    other => bail!(
+                        "expected {} value, found {}",
+                        ValType::$variant,
+                        other.ty()
+                    ),
+                }
+            }
+        }
+    )*)
+}
+
+try_from_val! {
+    i8 => S8(i) i,
+    i16 => S16(i) i,
+    i32 => I32(i) i,
+    i64 => I64(i) i,
+    u8 => U8(i) i,
+    u16 => U16(i) i,
+    u32 => U32(i) i,
+    u64 => U64(i) i,
+    f32 => F32(f) f32::from_bits(f),
+    f64 => F64(f) f64::from_bits(f),
+    String => String(s) s,
+}
+
+/// A borrowed argument to [`AdapterFunc::call_ref`](crate::AdapterFunc::call_ref).
+///
+/// Unlike a `Val`, a `ValRef` can describe a `string` argument without owning
+/// it, so strings can be passed to adapters without allocating a `String`.
+#[derive(Debug, Clone, Copy)]
+pub enum ValRef<'a> {
+    /// A reference to a value of any type.
+    Val(&'a Val),
+
+    /// A borrowed `string` value.
+    Str(&'a str),
+}
+
+impl ValRef<'_> {
+    /// Returns the corresponding [`ValType`] for this `ValRef`.
+    pub fn ty(&self) -> ValType {
+        match self {
+            ValRef::Val(val) => val.ty(),
+            ValRef::Str(_) => ValType::String,
+        }
+    }
+
+    /// Returns the `string` borrowed by this `ValRef`, if it is one.
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            ValRef::Val(Val::String(s)) => Some(s),
+            ValRef::Str(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// Copies this `ValRef` into an owned `Val`.
+    pub fn to_val(&self) -> Val {
+        match self {
+            ValRef::Val(val) => (*val).clone(),
+            ValRef::Str(s) => Val::String(s.to_string()),
+        }
+    }
+}
+
+impl<'a> From<&'a Val> for ValRef<'a> {
+    fn from(val: &'a Val) -> ValRef<'a> {
+        ValRef::Val(val)
+    }
+}
+
+impl<'a> From<&'a str> for ValRef<'a> {
+    fn from(val: &'a str) -> ValRef<'a> {
+        ValRef::Str(val)
     }
 }
 
@@ -303,12 +595,14 @@ pub(crate) fn into_checked_anyfunc(
         bail!("cross-`Store` values are not supported");
     }
     Ok(match val {
-        Val::AnyRef(AnyRef::Null) => wasmtime_runtime::VMCallerCheckedAnyfunc {
-            func_ptr: ptr::null(),
-            type_index: wasmtime_runtime::VMSharedSignatureIndex::default(),
-            vmctx: ptr::null_mut(),
-        },
-        Val::FuncRef(f) => {
+        Val::AnyRef(AnyRef::Null) | Val::FuncRef(None) => {
+            wasmtime_runtime::VMCallerCheckedAnyfunc {
+                func_ptr: ptr::null(),
+                type_index: wasmtime_runtime::VMSharedSignatureIndex::default(),
+                vmctx: ptr::null_mut(),
+            }
+        }
+        Val::FuncRef(Some(f)) => {
             let f = f.wasmtime_function();
             wasmtime_runtime::VMCallerCheckedAnyfunc {
                 func_ptr: f.address,
@@ -316,7 +610,7 @@ pub(crate) fn into_checked_anyfunc(
                 vmctx: f.vmctx,
             }
         }
-        _ => bail!("val is not funcref"),
+        other => bail!("tables can only hold funcref/anyref, got {:?}", other.ty()),
     })
 }
 
@@ -324,8 +618,15 @@ pub(crate) fn from_checked_anyfunc(
     item: wasmtime_runtime::VMCallerCheckedAnyfunc,
     store: &Store,
 ) -> Val {
-    if item.type_index == wasmtime_runtime::VMSharedSignatureIndex::default() {
-        Val::AnyRef(AnyRef::Null);
+    // Null entries are represented by `Val::null_funcref`, so that they're
+    // never turned into a `Func` pointing at nothing. Entries with a null
+    // function pointer are treated the same way, since there's no function to
+    // call through them either.
+    if item.type_index == wasmtime_runtime::VMSharedSignatureIndex::default()
+        || item.func_ptr.is_null()
+        || item.vmctx.is_null()
+    {
+        return Val::null_funcref();
     }
     let instance_handle = unsafe { wasmtime_runtime::InstanceHandle::from_vmctx(item.vmctx) };
     let export = wasmtime_runtime::ExportFunction {
@@ -334,5 +635,5 @@ pub(crate) fn from_checked_anyfunc(
         vmctx: item.vmctx,
     };
     let f = Func::from_wasmtime_function(export, store, instance_handle);
-    Val::FuncRef(f)
+    Val::FuncRef(Some(f))
 }
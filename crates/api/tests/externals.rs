@@ -1,5 +1,14 @@
 use wasmtime::*;
 
+/// A host function which does nothing.
+struct Nop;
+
+impl Callable for Nop {
+    fn call(&self, _params: &[Val], _results: &mut [Val]) -> Result<(), Trap> {
+        Ok(())
+    }
+}
+
 #[test]
 fn bad_globals() {
     let ty = GlobalType::new(ValType::I32, Mutability::Var);
@@ -18,9 +27,9 @@ fn bad_globals() {
 
 #[test]
 fn bad_tables() {
-    // i32 not supported yet
+    // mismatched initializer for a table of values
     let ty = TableType::new(ValType::I32, Limits::new(0, Some(1)));
-    assert!(Table::new(&Store::default(), ty.clone(), Val::I32(0)).is_err());
+    assert!(Table::new(&Store::default(), ty.clone(), Val::I64(0)).is_err());
 
     // mismatched initializer
     let ty = TableType::new(ValType::FuncRef, Limits::new(0, Some(1)));
@@ -38,6 +47,10 @@ fn bad_tables() {
     assert!(t.set(0, Val::I32(0)).is_err());
     assert!(t.set(0, Val::AnyRef(AnyRef::Null)).is_ok());
     assert!(t.set(1, Val::AnyRef(AnyRef::Null)).is_err());
+    let err = t.set(0, Val::String("hello".to_string())).unwrap_err();
+    assert_eq!(err.to_string(), "table holds funcref but got string");
+    let err = t.set(0, Val::I32(0)).unwrap_err();
+    assert_eq!(err.to_string(), "table holds funcref but got i32");
 
     // grow beyond max
     let ty = TableType::new(ValType::FuncRef, Limits::new(1, Some(1)));
@@ -49,8 +62,62 @@ fn bad_tables() {
     // grow wrong type
     let ty = TableType::new(ValType::FuncRef, Limits::new(1, Some(2)));
     let t = Table::new(&Store::default(), ty.clone(), Val::AnyRef(AnyRef::Null)).unwrap();
-    assert!(t.grow(1, Val::I32(0)).is_err());
+    let err = t.grow(1, Val::I32(0)).unwrap_err();
+    assert_eq!(err.to_string(), "table holds funcref but got i32");
     assert_eq!(t.size(), 1);
+
+    // non-null anyref in a funcref table
+    let ty = TableType::new(ValType::FuncRef, Limits::new(1, None));
+    let t = Table::new(&Store::default(), ty.clone(), Val::null_funcref()).unwrap();
+    let err = t.set(0, Val::AnyRef(AnyRef::new(Box::new(1)))).unwrap_err();
+    assert_eq!(err.to_string(), "table holds funcref but got anyref");
+}
+
+#[test]
+fn value_tables() -> anyhow::Result<()> {
+    let store = Store::default();
+    let ty = TableType::new(ValType::I32, Limits::new(2, Some(6)));
+    let t = Table::new(&store, ty, Val::I32(7))?;
+    assert_eq!(t.size(), 2);
+    assert_eq!(t.get(1).unwrap().unwrap_i32(), 7);
+    assert!(t.get(2).is_none());
+
+    t.set(0, Val::I32(1))?;
+    assert_eq!(t.replace(0, Val::I32(2))?.unwrap_i32(), 1);
+    assert_eq!(t.get(0).unwrap().unwrap_i32(), 2);
+    let err = t.set(0, Val::null()).unwrap_err();
+    assert_eq!(err.to_string(), "table holds i32 but got anyref");
+    assert!(t.set(2, Val::I32(0)).is_err());
+
+    assert_eq!(t.grow(3, Val::I32(9))?, 5);
+    assert_eq!(t.get(4).unwrap().unwrap_i32(), 9);
+    assert!(t.grow(2, Val::I32(0)).is_err());
+    assert_eq!(t.size(), 5);
+
+    t.fill(1, Val::I32(3), 3)?;
+    let vals = (0..5).map(|i| t.at(i).unwrap_i32()).collect::<Vec<_>>();
+    assert_eq!(vals, [2, 3, 3, 3, 9]);
+    assert!(t.fill(4, Val::I32(0), 2).is_err());
+    assert!(t.clear().is_err());
+
+    Table::copy(&t, 0, &t, 3, 2)?;
+    let vals = (0..5).map(|i| t.at(i).unwrap_i32()).collect::<Vec<_>>();
+    assert_eq!(vals, [3, 9, 3, 3, 9]);
+    Ok(())
+}
+
+#[test]
+fn large_null_table() -> anyhow::Result<()> {
+    let ty = TableType::new(ValType::FuncRef, Limits::new(100_000, None));
+    let t = Table::new(&Store::default(), ty, Val::AnyRef(AnyRef::Null))?;
+    assert_eq!(t.size(), 100_000);
+    for i in 0..t.size() {
+        match t.get(i) {
+            Some(Val::FuncRef(None)) => {}
+            _ => panic!("table entry {} should be null", i),
+        }
+    }
+    Ok(())
 }
 
 #[test]
@@ -66,7 +133,7 @@ fn cross_store() -> anyhow::Result<()> {
     let ty = GlobalType::new(ValType::I32, Mutability::Const);
     let global = Global::new(&store2, ty, Val::I32(0))?;
     let ty = MemoryType::new(Limits::new(1, None));
-    let memory = Memory::new(&store2, ty);
+    let memory = Memory::new(&store2, ty)?;
     let ty = TableType::new(ValType::FuncRef, Limits::new(1, None));
     let table = Table::new(&store2, ty, Val::AnyRef(AnyRef::Null))?;
 
@@ -84,8 +151,8 @@ fn cross_store() -> anyhow::Result<()> {
 
     // ============ Cross-store globals ==============
 
-    let store1val = Val::FuncRef(Func::wrap0(&store1, || {}));
-    let store2val = Val::FuncRef(Func::wrap0(&store2, || {}));
+    let store1val = Val::FuncRef(Some(Func::wrap0(&store1, || {})));
+    let store2val = Val::FuncRef(Some(Func::wrap0(&store2, || {})));
 
     let ty = GlobalType::new(ValType::FuncRef, Mutability::Var);
     assert!(Global::new(&store2, ty.clone(), store1val.clone()).is_err());
@@ -110,3 +177,967 @@ fn cross_store() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn memory_reader() -> anyhow::Result<()> {
+    use std::io::Read;
+
+    let store = Store::default();
+    let memory = Memory::new(&store, MemoryType::new(Limits::new(1, None)))?;
+    let size = memory.data_size();
+    unsafe {
+        memory.data_unchecked_mut()[10..14].copy_from_slice(&[1, 2, 3, 4]);
+    }
+
+    let mut reader = memory.reader(10);
+    let mut buf = [0; 4];
+    reader.read_exact(&mut buf)?;
+    assert_eq!(buf, [1, 2, 3, 4]);
+    assert_eq!(reader.offset(), 14);
+
+    let mut reader = memory.reader(size - 2);
+    let mut rest = Vec::new();
+    assert_eq!(reader.read_to_end(&mut rest)?, 2);
+    assert_eq!(reader.read(&mut buf)?, 0);
+
+    let mut reader = memory.reader(size + 100);
+    assert_eq!(reader.read(&mut buf)?, 0);
+    Ok(())
+}
+
+#[test]
+fn memory_writer() -> anyhow::Result<()> {
+    use std::io::{ErrorKind, Write};
+
+    let store = Store::default();
+    let memory = Memory::new(&store, MemoryType::new(Limits::new(1, None)))?;
+    let size = memory.data_size();
+
+    let mut writer = memory.writer(100);
+    writer.write_all(&[5, 6, 7, 8])?;
+    assert_eq!(writer.offset(), 104);
+    unsafe {
+        assert_eq!(&memory.data_unchecked()[99..105], &[0, 5, 6, 7, 8, 0]);
+    }
+
+    let mut writer = memory.writer(size - 2);
+    let err = writer.write_all(&[1, 2, 3]).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::WriteZero);
+    unsafe {
+        assert_eq!(&memory.data_unchecked()[size - 2..], &[1, 2]);
+    }
+    Ok(())
+}
+
+#[test]
+fn table_replace() -> anyhow::Result<()> {
+    let store = Store::default();
+    let ty = TableType::new(ValType::FuncRef, Limits::new(1, None));
+    let t = Table::new(&store, ty, Val::AnyRef(AnyRef::Null))?;
+    let f = Func::wrap0(&store, || 1i32);
+
+    match t.replace(0, f.into())? {
+        Val::FuncRef(None) => {}
+        _ => panic!("expected the initial null element"),
+    }
+    let prev = t.replace(0, Val::AnyRef(AnyRef::Null))?;
+    let prev = prev.funcref().expect("expected the function we stored");
+    assert_eq!(prev.call(&[])?[0].unwrap_i32(), 1);
+
+    assert!(t.replace(1, Val::AnyRef(AnyRef::Null)).is_err());
+    Ok(())
+}
+
+#[test]
+fn memory_store_blob() -> anyhow::Result<()> {
+    let store = Store::default();
+    let memory = Memory::new(&store, MemoryType::new(Limits::new(1, Some(4))))?;
+    let blob = (0..100_000).map(|i| i as u8).collect::<Vec<_>>();
+
+    assert!(memory.store_blob(10, &blob, false).is_err());
+    assert_eq!(memory.size(), 1);
+
+    memory.store_blob(10, &blob, true)?;
+    assert_eq!(memory.size(), 2);
+    unsafe {
+        assert_eq!(&memory.data_unchecked()[10..10 + blob.len()], &blob[..]);
+    }
+
+    // growing beyond the maximum fails without writing anything
+    let offset = memory.data_size() * 2;
+    assert!(memory.store_blob(offset, &blob, true).is_err());
+    assert_eq!(memory.size(), 2);
+    Ok(())
+}
+
+#[cfg(debug_assertions)]
+#[test]
+fn memory_instance_refcount() {
+    let store = Store::default();
+    let memory = Memory::new(&store, MemoryType::new(Limits::new(1, None))).unwrap();
+    let before = memory.debug_instance_refcount();
+    let clone = memory.clone();
+    assert_eq!(memory.debug_instance_refcount(), before + 1);
+    drop(clone);
+    assert_eq!(memory.debug_instance_refcount(), before);
+}
+
+#[test]
+fn table_copy_cross_store() -> anyhow::Result<()> {
+    let store1 = Store::default();
+    let store2 = Store::default();
+    let ty = TableType::new(ValType::FuncRef, Limits::new(4, None));
+    let t1 = Table::new(&store1, ty.clone(), Val::AnyRef(AnyRef::Null))?;
+    let t2 = Table::new(&store2, ty.clone(), Val::AnyRef(AnyRef::Null))?;
+
+    // null references have no store affinity and may be copied
+    assert!(Table::copy(&t2, 0, &t1, 0, 4).is_err());
+    Table::copy_cross_store(&t2, 0, &t1, 0, 4)?;
+    assert!(Table::copy_cross_store(&t2, 0, &t1, 2, 4).is_err());
+
+    // ... but real functions may not
+    t1.set(1, Func::wrap0(&store1, || {}).into())?;
+    assert!(Table::copy_cross_store(&t2, 0, &t1, 0, 4).is_err());
+    Table::copy_cross_store(&t2, 2, &t1, 2, 2)?;
+    Ok(())
+}
+
+#[test]
+fn memory_raw_parts() -> anyhow::Result<()> {
+    let store = Store::default();
+    let memory = Memory::new(&store, MemoryType::new(Limits::new(1, None)))?;
+    let (ptr, len) = memory.raw_parts();
+    assert_eq!(ptr, memory.data_ptr());
+    assert_eq!(len, memory.data_size());
+
+    memory.grow(1)?;
+    let (_, len) = memory.raw_parts();
+    assert_eq!(len, memory.data_size());
+    Ok(())
+}
+
+#[test]
+fn memory_ptr_range() -> anyhow::Result<()> {
+    let store = Store::default();
+    let memory = Memory::new(&store, MemoryType::new(Limits::new(1, None)))?;
+    let range = memory.ptr_range();
+    assert_eq!(range.start, memory.data_ptr());
+    assert_eq!(
+        range.end as usize - range.start as usize,
+        memory.data_size()
+    );
+
+    memory.grow(2)?;
+    let range = memory.ptr_range();
+    assert_eq!(
+        range.end as usize - range.start as usize,
+        memory.data_size()
+    );
+    Ok(())
+}
+
+#[test]
+fn memory_guard() {
+    fn checksum(data: impl AsRef<[u8]>) -> u32 {
+        data.as_ref().iter().map(|b| u32::from(*b)).sum()
+    }
+
+    let store = Store::default();
+    let ty = MemoryType::new(Limits::new(1, None));
+    let mem = Memory::new(&store, ty).unwrap();
+    mem.store_blob(10, &[1, 2, 3], false).unwrap();
+
+    let guard = unsafe { mem.guard() };
+    assert_eq!(guard.len(), mem.data_size());
+    assert_eq!(&guard[10..13], &[1, 2, 3]);
+    assert_eq!(checksum(&*guard), 6);
+    assert_eq!(checksum(guard), 6);
+}
+
+#[test]
+fn memory_with_slice() {
+    let store = Store::default();
+    let ty = MemoryType::new(Limits::new(1, None));
+    let mem = Memory::new(&store, ty).unwrap();
+    mem.store_blob(10, &[1, 2, 3], false).unwrap();
+
+    let (len, bytes) = unsafe { mem.with_slice(|data| (data.len(), data[10..13].to_vec())) };
+    assert_eq!(len, mem.data_size());
+    assert_eq!(bytes, [1, 2, 3]);
+
+    unsafe { mem.with_slice_mut(|data| data[11] = 20) };
+    assert_eq!(unsafe { mem.with_slice(|data| data[11]) }, 20);
+}
+
+#[test]
+fn memory_write_vals() -> anyhow::Result<()> {
+    let store = Store::default();
+    let mem = Memory::new(&store, MemoryType::new(Limits::new(1, None)))?;
+    mem.store_blob(0, &[0xff; 32], false)?;
+
+    let vals = [Val::I32(0x0403_0201), Val::I64(0x0c0b_0a09_0807_0605)];
+    assert_eq!(mem.write_vals(8, &vals)?, 16);
+    let data = unsafe { &mem.data_unchecked()[8..24] };
+    assert_eq!(data, [1, 2, 3, 4, 0, 0, 0, 0, 5, 6, 7, 8, 9, 10, 11, 12]);
+
+    // Smaller values are packed without padding.
+    let vals = [Val::U8(1), Val::U8(2), Val::S16(-1)];
+    assert_eq!(mem.write_vals(0, &vals)?, 4);
+    assert_eq!(unsafe { &mem.data_unchecked()[..4] }, [1, 2, 0xff, 0xff]);
+
+    let err = mem
+        .write_vals(0, &[Val::I32(0), Val::String("x".to_string())])
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "cannot write a value of type string to memory"
+    );
+    assert!(mem.write_vals(mem.data_size() - 4, &[Val::I64(0)]).is_err());
+    assert_eq!(unsafe { &mem.data_unchecked()[..4] }, [1, 2, 0xff, 0xff]);
+    Ok(())
+}
+
+#[test]
+fn memory_read_vals() -> anyhow::Result<()> {
+    let store = Store::default();
+    let mem = Memory::new(&store, MemoryType::new(Limits::new(1, None)))?;
+
+    let vals = [
+        Val::U8(7),
+        Val::I32(-3),
+        Val::F64(2.5f64.to_bits()),
+        Val::S16(-2),
+        Val::V128(0x0102_0304_0506_0708_090a_0b0c_0d0e_0f10),
+    ];
+    mem.write_vals(16, &vals)?;
+    let tys = vals.iter().map(|v| v.ty()).collect::<Vec<_>>();
+    assert_eq!(mem.read_vals(16, &tys)?, vals);
+
+    let err = mem.read_vals(0, &[ValType::AnyRef]).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "cannot read a value of type anyref from memory"
+    );
+    assert!(mem.read_vals(mem.data_size() - 4, &[ValType::I64]).is_err());
+    Ok(())
+}
+
+#[test]
+fn memory_scalars() -> anyhow::Result<()> {
+    let store = Store::default();
+    let mem = Memory::new(&store, MemoryType::new(Limits::new(1, None)))?;
+
+    mem.store_i32(4, -2)?;
+    assert_eq!(mem.load_i32(4)?, -2);
+    assert_eq!(
+        unsafe { &mem.data_unchecked()[4..8] },
+        [0xfe, 0xff, 0xff, 0xff]
+    );
+    mem.store_i64(9, 0x0102_0304_0506_0708)?;
+    assert_eq!(mem.load_i64(9)?, 0x0102_0304_0506_0708);
+    assert_eq!(mem.load_i32(9)?, 0x0506_0708);
+    mem.store_f32(20, 1.5)?;
+    assert_eq!(mem.load_f32(20)?, 1.5);
+    mem.store_f64(24, -0.25)?;
+    assert_eq!(mem.load_f64(24)?, -0.25);
+
+    let end = mem.data_size();
+    mem.store_i32(end - 4, 7)?;
+    assert_eq!(mem.load_i32(end - 4)?, 7);
+    assert!(mem.load_i32(end - 3).is_err());
+    assert!(mem.store_i64(end - 4, 0).is_err());
+    assert!(mem.load_f64(usize::max_value()).is_err());
+    assert_eq!(mem.load_i32(end - 4)?, 7);
+    Ok(())
+}
+
+#[test]
+fn memory_cursor() -> anyhow::Result<()> {
+    let store = Store::default();
+    let mem = Memory::new(&store, MemoryType::new(Limits::new(1, None)))?;
+
+    let mut cursor = mem.cursor(8);
+    assert_eq!(cursor.position(), 8);
+    assert_eq!(cursor.write_u8(1)?, 9);
+    assert_eq!(cursor.write_i32(-2)?, 13);
+    assert_eq!(cursor.write_str("hi")?, 15);
+    assert_eq!(cursor.write_i64(3)?, 23);
+    assert_eq!(cursor.write_f32(1.5)?, 27);
+    assert_eq!(cursor.write_f64(-0.25)?, 35);
+    assert_eq!(cursor.write_bytes(&[])?, 35);
+
+    assert_eq!(unsafe { mem.data_unchecked()[8] }, 1);
+    assert_eq!(mem.load_i32(9)?, -2);
+    assert_eq!(unsafe { &mem.data_unchecked()[13..15] }, b"hi");
+    assert_eq!(mem.load_i64(15)?, 3);
+    assert_eq!(mem.load_f32(23)?, 1.5);
+    assert_eq!(mem.load_f64(27)?, -0.25);
+
+    // Writes which don't fit fail without writing or moving the cursor.
+    let end = mem.data_size();
+    let mut cursor = mem.cursor(end - 2);
+    assert!(cursor.write_i32(7).is_err());
+    assert_eq!(cursor.position(), end - 2);
+    assert_eq!(cursor.write_str("ok")?, end);
+    assert!(cursor.write_u8(0).is_err());
+    assert_eq!(unsafe { &mem.data_unchecked()[end - 2..] }, b"ok");
+    Ok(())
+}
+
+#[test]
+fn memory_grow_reports_relocation() -> anyhow::Result<()> {
+    let store = Store::default();
+
+    // Memories with a small maximum reserve all of their address space up
+    // front, so they always grow in place.
+    let mem = Memory::new(&store, MemoryType::new(Limits::new(1, Some(4))))?;
+    let base = mem.data_ptr();
+    let grown = mem.grow(2)?;
+    assert_eq!(
+        grown,
+        GrowResult {
+            prev_pages: 1,
+            relocated: false
+        }
+    );
+    assert_eq!(mem.data_ptr(), base);
+
+    // Whether other memories move depends on the platform, but the result
+    // must always agree with the base pointer.
+    let mem = Memory::new(&store, MemoryType::new(Limits::new(1, None)))?;
+    for _ in 0..3 {
+        let base = mem.data_ptr();
+        let grown = mem.grow(16)?;
+        assert_eq!(grown.relocated, mem.data_ptr() != base);
+    }
+
+    #[allow(deprecated)]
+    let prev = mem.grow_pages(1)?;
+    assert_eq!(prev, 49);
+    Ok(())
+}
+
+#[test]
+fn memory_growable_pages() -> anyhow::Result<()> {
+    let store = Store::default();
+    let ty = MemoryType::new(Limits::new(1, Some(3)));
+    let mem = Memory::new(&store, ty)?;
+    assert_eq!(mem.growable_pages(), Some(2));
+    mem.grow(2)?;
+    assert_eq!(mem.growable_pages(), Some(0));
+    assert!(mem.grow(1).is_err());
+
+    let ty = MemoryType::new(Limits::new(1, None));
+    let mem = Memory::new(&store, ty)?;
+    assert_eq!(mem.growable_pages(), None);
+    Ok(())
+}
+
+#[test]
+fn memory_on_grow() -> anyhow::Result<()> {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    let store = Store::default();
+    let ty = MemoryType::new(Limits::new(1, Some(4)));
+    let mem = Memory::new(&store, ty)?;
+    let seen = Rc::new(RefCell::new(Vec::new()));
+    let seen2 = seen.clone();
+    mem.on_grow(move |old, new| seen2.borrow_mut().push((old, new)));
+
+    mem.grow(2)?;
+    mem.clone().grow(1)?;
+    assert!(mem.grow(2).is_err());
+    assert_eq!(*seen.borrow(), [(1, 3), (3, 4)]);
+    Ok(())
+}
+
+#[test]
+fn table_ensure_size() -> anyhow::Result<()> {
+    let store = Store::default();
+    let ty = TableType::new(ValType::FuncRef, Limits::new(2, Some(10)));
+    let table = Table::new(&store, ty, Val::null_funcref())?;
+
+    assert_eq!(table.ensure_size(5, Val::null_funcref())?, 5);
+    assert_eq!(table.size(), 5);
+    assert_eq!(table.ensure_size(3, Val::null_funcref())?, 5);
+    assert_eq!(table.ensure_size(5, Val::null_funcref())?, 5);
+    assert_eq!(table.size(), 5);
+    assert!(table.ensure_size(11, Val::null_funcref()).is_err());
+    assert_eq!(table.size(), 5);
+    Ok(())
+}
+
+#[test]
+fn memory_with_data() -> anyhow::Result<()> {
+    let store = Store::default();
+    let data = (0..70_000).map(|i| i as u8).collect::<Vec<u8>>();
+
+    let ty = MemoryType::new(Limits::new(1, Some(4)));
+    let mem = Memory::with_data(&store, ty, &data)?;
+    assert_eq!(mem.size(), 2);
+    unsafe {
+        assert_eq!(&mem.data_unchecked()[..data.len()], &data[..]);
+        assert!(mem.data_unchecked()[data.len()..].iter().all(|b| *b == 0));
+    }
+
+    let ty = MemoryType::new(Limits::new(3, None));
+    let mem = Memory::with_data(&store, ty, &[1, 2, 3])?;
+    assert_eq!(mem.size(), 3);
+    unsafe {
+        assert_eq!(&mem.data_unchecked()[..4], &[1, 2, 3, 0]);
+    }
+
+    let ty = MemoryType::new(Limits::new(0, Some(1)));
+    assert!(Memory::with_data(&store, ty, &data).is_err());
+    Ok(())
+}
+
+#[test]
+fn table_clear() -> anyhow::Result<()> {
+    let store = Store::default();
+    let ty = TableType::new(ValType::FuncRef, Limits::new(3, None));
+    let f = Func::wrap0(&store, || {});
+    let table = Table::new(&store, ty, f.clone().into())?;
+    table.grow(2, Val::null_funcref())?;
+    table.set(4, f.into())?;
+    assert!(!table.get(0).unwrap().is_null());
+
+    table.clear()?;
+    assert_eq!(table.size(), 5);
+    for i in 0..table.size() {
+        assert!(table.get(i).unwrap().is_null());
+    }
+    Ok(())
+}
+
+#[test]
+fn table_clear_anyref() -> anyhow::Result<()> {
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    struct Counted(Rc<Cell<usize>>);
+
+    impl Drop for Counted {
+        fn drop(&mut self) {
+            self.0.set(self.0.get() + 1);
+        }
+    }
+
+    let store = Store::default();
+    let drops = Rc::new(Cell::new(0));
+    let ty = TableType::new(ValType::AnyRef, Limits::new(3, None));
+    let r = AnyRef::new(Box::new(Counted(drops.clone())));
+    let table = Table::new(&store, ty, Val::AnyRef(r))?;
+    let r = AnyRef::new(Box::new(Counted(drops.clone())));
+    table.grow(2, Val::AnyRef(r))?;
+    for i in 0..table.size() {
+        assert!(!table.get(i).unwrap().is_null());
+    }
+    assert_eq!(drops.get(), 0);
+
+    table.clear()?;
+    assert_eq!(table.size(), 5);
+    for i in 0..table.size() {
+        assert!(table.get(i).unwrap().is_null());
+    }
+    assert_eq!(drops.get(), 2);
+    Ok(())
+}
+
+#[test]
+fn table_is_empty_and_at() -> anyhow::Result<()> {
+    let store = Store::default();
+    let ty = TableType::new(ValType::FuncRef, Limits::new(0, None));
+    let table = Table::new(&store, ty, Val::null_funcref())?;
+    assert!(table.is_empty());
+
+    let f = Func::wrap0(&store, || {});
+    table.grow(2, Val::null_funcref())?;
+    table.set(1, f.into())?;
+    assert!(!table.is_empty());
+    assert!(table.at(0).is_null());
+    assert!(table.at(1).funcref().is_some());
+    assert!(table.get(2).is_none());
+    Ok(())
+}
+
+#[test]
+fn table_uninitialized_funcref_is_null() -> anyhow::Result<()> {
+    let store = Store::default();
+    let module = Module::new(
+        &store,
+        r#"
+            (module
+                (table (export "t") 3 funcref)
+                (func $f)
+                (elem (i32.const 1) $f))
+        "#,
+    )?;
+    let instance = Instance::new(&module, &[])?;
+    let table = instance.get_table("t").unwrap();
+
+    for i in [0, 2].iter() {
+        let val = table.get(*i).unwrap();
+        assert!(val.is_null());
+        assert!(val.funcref().is_none());
+    }
+    let val = table.get(1).unwrap();
+    assert!(!val.is_null());
+    assert!(val.funcref().is_some());
+    Ok(())
+}
+
+#[test]
+#[should_panic(expected = "table index 2 out of bounds for table of size 2")]
+fn table_at_out_of_bounds() {
+    let store = Store::default();
+    let ty = TableType::new(ValType::FuncRef, Limits::new(2, None));
+    let table = Table::new(&store, ty, Val::null_funcref()).unwrap();
+    table.at(2);
+}
+
+#[test]
+fn memory_matches_import() -> anyhow::Result<()> {
+    let store = Store::default();
+    let import = |min, max| {
+        ImportType::new(
+            "env",
+            "memory",
+            ExternType::Memory(MemoryType::new(Limits::new(min, max))),
+        )
+    };
+
+    // a larger memory with a tighter maximum satisfies the import
+    let mem: Extern = Memory::new(&store, MemoryType::new(Limits::new(2, Some(3))))?.into();
+    assert!(mem.matches_import(&import(1, None)).is_ok());
+    assert!(mem.matches_import(&import(1, Some(4))).is_ok());
+    assert!(mem.matches_import(&import(2, Some(3))).is_ok());
+
+    // too small, or a maximum that's too large or missing
+    let err = mem.matches_import(&import(3, None)).unwrap_err();
+    assert!(err.contains("env::memory"), "{}", err);
+    assert!(mem.matches_import(&import(1, Some(2))).is_err());
+    let unbounded: Extern = Memory::new(&store, MemoryType::new(Limits::new(1, None)))?.into();
+    assert!(unbounded.matches_import(&import(1, Some(2))).is_err());
+
+    // growing the memory is taken into account
+    unbounded.memory().unwrap().grow(2)?;
+    assert!(unbounded.matches_import(&import(3, None)).is_ok());
+
+    // wrong kind of extern
+    let global: Extern = Global::new(
+        &store,
+        GlobalType::new(ValType::I32, Mutability::Const),
+        Val::I32(0),
+    )?
+    .into();
+    assert!(global.matches_import(&import(0, None)).is_err());
+    Ok(())
+}
+
+#[test]
+fn memory_grow_to() -> anyhow::Result<()> {
+    let store = Store::default();
+    let mem = Memory::new(&store, MemoryType::new(Limits::new(1, Some(4))))?;
+
+    assert_eq!(mem.grow_to(3)?, 1);
+    assert_eq!(mem.size(), 3);
+
+    assert_eq!(mem.grow_to(2)?, 3);
+    assert_eq!(mem.grow_to(3)?, 3);
+    assert_eq!(mem.size(), 3);
+
+    let err = mem.grow_to(5).unwrap_err();
+    assert!(err.to_string().contains("maximum is 4 pages"), "{}", err);
+    assert_eq!(mem.size(), 3);
+    Ok(())
+}
+
+#[test]
+fn memory_snapshot() -> anyhow::Result<()> {
+    let store = Store::default();
+    let mem = Memory::new(&store, MemoryType::new(Limits::new(1, Some(3))))?;
+    mem.store_blob(100, b"saved", false)?;
+    let snapshot = mem.take_snapshot();
+    assert_eq!(snapshot.len(), mem.data_size());
+
+    // Scribble over the memory, grow it, and then restore the snapshot.
+    mem.store_blob(100, b"xxxxx", false)?;
+    mem.grow(1)?;
+    mem.store_blob(70_000, b"junk", false)?;
+    mem.restore_snapshot(&snapshot)?;
+    assert_eq!(mem.size(), 2);
+    assert_eq!(&mem.take_snapshot()[..snapshot.len()], &snapshot[..]);
+    assert!(mem.take_snapshot()[snapshot.len()..]
+        .iter()
+        .all(|b| *b == 0));
+
+    // Restoring into a smaller memory grows it.
+    let other = Memory::new(&store, MemoryType::new(Limits::new(0, None)))?;
+    other.restore_snapshot(&snapshot)?;
+    assert_eq!(other.take_snapshot(), snapshot);
+
+    // Snapshots larger than the maximum are rejected without modification.
+    let small = Memory::new(&store, MemoryType::new(Limits::new(0, Some(0))))?;
+    assert!(small.restore_snapshot(&snapshot).is_err());
+    assert_eq!(small.data_size(), 0);
+    Ok(())
+}
+
+#[test]
+fn memory_contents_eq() -> anyhow::Result<()> {
+    let store = Store::default();
+    let a = Memory::new(&store, MemoryType::new(Limits::new(1, None)))?;
+    let b = Memory::new(&store, MemoryType::new(Limits::new(1, None)))?;
+    assert!(a.contents_eq(&b));
+    assert!(a.contents_eq(&a));
+
+    a.store_blob(10, b"hello", false)?;
+    assert!(!a.contents_eq(&b));
+    b.restore_snapshot(&a.take_snapshot())?;
+    assert!(a.contents_eq(&b));
+
+    // Differently sized memories are never equal, even if the common prefix is.
+    b.grow(1)?;
+    assert!(!a.contents_eq(&b));
+    assert!(!b.contents_eq(&a));
+    Ok(())
+}
+
+#[test]
+fn host_memory_shared_with_wasm() -> anyhow::Result<()> {
+    let store = Store::default();
+    let memory = Memory::new(&store, MemoryType::new(Limits::new(1, None)))?;
+    let module = Module::new(
+        &store,
+        r#"
+            (module
+                (import "" "mem" (memory 1))
+                (func (export "load") (param i32) (result i32)
+                    (i32.load8_u (local.get 0)))
+                (func (export "store") (param i32 i32)
+                    (i32.store8 (local.get 0) (local.get 1)))
+                (func (export "grow") (param i32) (result i32)
+                    (memory.grow (local.get 0))))
+        "#,
+    )?;
+    let instance = Instance::new(&module, &[memory.clone().into()])?;
+    let load = instance.get_export("load").unwrap().func().unwrap().clone();
+    let store_byte = instance
+        .get_export("store")
+        .unwrap()
+        .func()
+        .unwrap()
+        .clone();
+    let grow = instance.get_export("grow").unwrap().func().unwrap().clone();
+
+    // Host writes are visible to wasm.
+    unsafe {
+        memory.data_unchecked_mut()[100] = 42;
+    }
+    assert_eq!(load.call(&[100.into()])?[0].unwrap_i32(), 42);
+
+    // Wasm writes are visible to the host.
+    store_byte.call(&[200.into(), 7.into()])?;
+    assert_eq!(unsafe { memory.data_unchecked()[200] }, 7);
+
+    // Growth performed by wasm is observed by the host, which can then write
+    // into the new page for wasm to read.
+    assert_eq!(grow.call(&[1.into()])?[0].unwrap_i32(), 1);
+    assert_eq!(memory.size(), 2);
+    assert_eq!(memory.data_size(), 2 * 65536);
+    assert_eq!(unsafe { memory.data_unchecked()[100] }, 42);
+    unsafe {
+        memory.data_unchecked_mut()[65536 + 1] = 9;
+    }
+    assert_eq!(load.call(&[(65536 + 1).into()])?[0].unwrap_i32(), 9);
+    Ok(())
+}
+
+#[test]
+fn memory_page_size() {
+    let store = Store::default();
+    let memory = Memory::new(&store, MemoryType::new(Limits::new(3, None))).unwrap();
+    assert_eq!(Memory::PAGE_SIZE, 65536);
+    assert_eq!(
+        memory.size() as usize * Memory::PAGE_SIZE,
+        memory.data_size()
+    );
+}
+
+#[test]
+fn table_funcref_signature() -> anyhow::Result<()> {
+    let store = Store::default();
+    let i32_to_i32 = FuncType::from_vecs(vec![ValType::I32], vec![ValType::I32]);
+
+    // A host function stored in a host table.
+    let ty = TableType::new(ValType::FuncRef, Limits::new(1, None));
+    let table = Table::new(&store, ty, Val::null_funcref())?;
+    table.set(0, Func::wrap1(&store, |x: i32| x + 1).into())?;
+    let f = table.at(0).funcref().expect("expected a function").clone();
+    assert_eq!(*f.ty(), i32_to_i32);
+    assert_eq!(f.call(&[Val::I32(41)])?[0].unwrap_i32(), 42);
+
+    // A wasm function placed in a table by an element segment.
+    let module = Module::new(
+        &store,
+        r#"
+            (module
+                (table (export "t") 1 funcref)
+                (func $double (param i32) (result i32)
+                    local.get 0
+                    i32.const 2
+                    i32.mul)
+                (elem (i32.const 0) $double))
+        "#,
+    )?;
+    let instance = Instance::new(&module, &[])?;
+    let table = instance.exports()[0].table().unwrap();
+    let f = table.at(0).funcref().expect("expected a function").clone();
+    assert_eq!(*f.ty(), i32_to_i32);
+    assert_eq!(f.call(&[Val::I32(21)])?[0].unwrap_i32(), 42);
+    Ok(())
+}
+
+#[test]
+fn table_roots_host_funcs() -> anyhow::Result<()> {
+    let store = Store::default();
+    let ty = TableType::new(ValType::FuncRef, Limits::new(2, None));
+    let table = Table::new(&store, ty, Val::null_funcref())?;
+
+    // The table keeps the host function alive after our handle is dropped.
+    let f = Func::wrap1(&store, |x: i32| x * 3);
+    table.set(0, f.into())?;
+    table.grow(1, Func::wrap1(&store, |x: i32| x - 1).into())?;
+    Table::copy(&table, 1, &table, 0, 1)?;
+    let f = table.at(0).funcref().expect("expected a function").clone();
+    assert_eq!(f.call(&[Val::I32(14)])?[0].unwrap_i32(), 42);
+    let f = table.at(1).funcref().expect("expected a function").clone();
+    assert_eq!(f.call(&[Val::I32(14)])?[0].unwrap_i32(), 42);
+    let f = table.at(2).funcref().expect("expected a function").clone();
+    assert_eq!(f.call(&[Val::I32(43)])?[0].unwrap_i32(), 42);
+
+    // Overwriting an element releases its function, but handles read from the
+    // table beforehand stay valid.
+    table.clear()?;
+    assert!(table.at(0).is_null());
+    assert_eq!(f.call(&[Val::I32(43)])?[0].unwrap_i32(), 42);
+    Ok(())
+}
+
+#[test]
+fn table_roots_anyref() -> anyhow::Result<()> {
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    struct Counted(u32, Rc<Cell<usize>>);
+
+    impl Drop for Counted {
+        fn drop(&mut self) {
+            self.1.set(self.1.get() + 1);
+        }
+    }
+
+    let store = Store::default();
+    let drops = Rc::new(Cell::new(0));
+    let ty = TableType::new(ValType::AnyRef, Limits::new(3, None));
+    let table = Table::new(&store, ty, Val::null_anyref())?;
+
+    // The table keeps the host object alive after our handle is dropped.
+    let r = AnyRef::new(Box::new(Counted(42, drops.clone())));
+    table.set(0, Val::AnyRef(r))?;
+    let r = table.at(0).anyref().expect("expected an anyref");
+    assert_eq!(r.data().downcast_ref::<Counted>().unwrap().0, 42);
+    drop(r);
+    Table::copy(&table, 2, &table, 0, 1)?;
+    assert_eq!(drops.get(), 0);
+
+    // Overwriting an element releases its reference, once no other element
+    // refers to it.
+    let prev = table.replace(0, Val::null_anyref())?;
+    assert!(prev
+        .anyref()
+        .unwrap()
+        .ptr_eq(&table.at(2).anyref().unwrap()));
+    drop(prev);
+    assert!(table.at(0).is_null());
+    assert_eq!(drops.get(), 0);
+    table.set(2, Val::null_anyref())?;
+    assert_eq!(drops.get(), 1);
+
+    // Dropping the table releases the references it still holds.
+    let r = AnyRef::new(Box::new(Counted(7, drops.clone())));
+    table.fill(0, Val::AnyRef(r), 3)?;
+    assert_eq!(drops.get(), 1);
+    drop(table);
+    assert_eq!(drops.get(), 2);
+    Ok(())
+}
+
+#[test]
+fn global_roots_anyref() -> anyhow::Result<()> {
+    let store = Store::default();
+    let ty = GlobalType::new(ValType::AnyRef, Mutability::Var);
+    let r = AnyRef::new(Box::new(42u32));
+    let global = Global::new(&store, ty, Val::AnyRef(r.clone()))?;
+    drop(r);
+
+    let r = global.get().anyref().expect("expected an anyref");
+    assert_eq!(r.data().downcast_ref::<u32>(), Some(&42));
+    global.set(Val::AnyRef(AnyRef::Null))?;
+    assert_eq!(r.data().downcast_ref::<u32>(), Some(&42));
+    Ok(())
+}
+
+#[test]
+fn extern_type_display() {
+    let func = FuncType::from_vecs(vec![ValType::I32], vec![ValType::I32]);
+    assert_eq!(ExternType::Func(func).to_string(), "func (i32) -> i32");
+    let func = FuncType::from_vecs(vec![ValType::I64, ValType::F32], vec![]);
+    assert_eq!(ExternType::Func(func).to_string(), "func (i64, f32)");
+    let func = FuncType::from_vecs(vec![], vec![ValType::I32, ValType::F64]);
+    assert_eq!(ExternType::Func(func).to_string(), "func () -> (i32, f64)");
+
+    let global = GlobalType::new(ValType::F64, Mutability::Const);
+    assert_eq!(ExternType::Global(global).to_string(), "global f64");
+    let global = GlobalType::new(ValType::I32, Mutability::Var);
+    assert_eq!(ExternType::Global(global).to_string(), "global mut i32");
+
+    let table = TableType::new(ValType::FuncRef, Limits::new(2, None));
+    assert_eq!(
+        ExternType::Table(table).to_string(),
+        "table funcref {min 2}"
+    );
+
+    let memory = MemoryType::new(Limits::new(1, Some(3)));
+    assert_eq!(
+        ExternType::Memory(memory).to_string(),
+        "memory {min 1, max 3}"
+    );
+
+    let adapter = AdapterType::new(Box::new([ValType::S32]), Box::new([ValType::String]));
+    assert_eq!(
+        ExternType::Adapter(adapter).to_string(),
+        "adapter (s32) -> string"
+    );
+}
+
+#[test]
+fn extern_type_eq() {
+    let memory = |max| ExternType::Memory(MemoryType::new(Limits::new(1, max)));
+    assert_eq!(memory(Some(3)), memory(Some(3)));
+    assert_ne!(memory(Some(3)), memory(None));
+
+    let store = Store::default();
+    let f = Func::wrap1(&store, |x: i32| x);
+    let func = FuncType::from_vecs(vec![ValType::I32], vec![ValType::I32]);
+    assert_eq!(Extern::from(f).ty(), ExternType::Func(func));
+    assert_ne!(
+        memory(None),
+        ExternType::Func(FuncType::from_vecs(vec![], vec![]))
+    );
+}
+
+#[test]
+fn table_copy_element_mismatch() -> anyhow::Result<()> {
+    let store = Store::default();
+    let funcs = Table::new(
+        &store,
+        TableType::new(ValType::FuncRef, Limits::new(2, None)),
+        Func::wrap0(&store, || {}).into(),
+    )?;
+    let anys = Table::new(
+        &store,
+        TableType::new(ValType::AnyRef, Limits::new(2, None)),
+        Val::null_anyref(),
+    )?;
+    assert!(anys.at(0).is_null());
+
+    let err = Table::copy(&anys, 0, &funcs, 0, 1).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "cannot copy funcref elements into a table of anyref"
+    );
+    let err = Table::copy(&funcs, 0, &anys, 0, 1).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "cannot copy anyref elements into a table of funcref"
+    );
+    assert!(Table::copy_cross_store(&funcs, 0, &anys, 0, 1).is_err());
+    assert!(funcs.at(0).funcref().is_some());
+
+    // Copies between tables of the same element type still work.
+    let other = Table::new(
+        &store,
+        TableType::new(ValType::FuncRef, Limits::new(1, None)),
+        Val::null_funcref(),
+    )?;
+    Table::copy(&other, 0, &funcs, 0, 1)?;
+    assert!(other.at(0).funcref().is_some());
+    Ok(())
+}
+
+#[test]
+fn table_copy_out_of_bounds_names_operation() -> anyhow::Result<()> {
+    let store = Store::default();
+    let ty = TableType::new(ValType::FuncRef, Limits::new(4, None));
+    let src = Table::new(&store, ty.clone(), Val::AnyRef(AnyRef::Null))?;
+    let dst = Table::new(&store, ty, Val::AnyRef(AnyRef::Null))?;
+
+    let err = Table::copy(&dst, 3, &src, 0, 2).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Table::copy dst+len out of bounds: 3 + 2 exceeds table size 4"
+    );
+    let err = Table::copy(&dst, 0, &src, 3, 2).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Table::copy src+len out of bounds: 3 + 2 exceeds table size 4"
+    );
+    let err = Table::copy_cross_store(&dst, 0, &src, u32::max_value(), 2).unwrap_err();
+    assert!(
+        err.to_string()
+            .starts_with("Table::copy_cross_store src+len"),
+        "{}",
+        err
+    );
+    Ok(())
+}
+
+#[test]
+fn extern_into_variants() -> anyhow::Result<()> {
+    let store = Store::default();
+    let func = Extern::from(Func::wrap0(&store, || {}));
+    let global = Extern::from(Global::new(
+        &store,
+        GlobalType::new(ValType::I32, Mutability::Const),
+        Val::I32(1),
+    )?);
+    let table = Extern::from(Table::new(
+        &store,
+        TableType::new(ValType::FuncRef, Limits::new(1, None)),
+        Val::null_funcref(),
+    )?);
+    let memory = Extern::from(Memory::new(&store, MemoryType::new(Limits::new(1, None)))?);
+
+    let adapter = Extern::Adapter(AdapterFunc::new(
+        &store,
+        AdapterType::new(Box::new([]), Box::new([])),
+        std::rc::Rc::new(Nop),
+    ));
+
+    // Failed conversions hand back the original extern, which can then be
+    // converted into the right type.
+    let func = func.into_memory().err().unwrap();
+    assert!(func.into_func().ok().unwrap().ty().params().is_empty());
+    let global = global.into_func().err().unwrap();
+    assert_eq!(global.into_global().ok().unwrap().get().unwrap_i32(), 1);
+    let table = table.into_global().err().unwrap();
+    assert_eq!(table.into_table().ok().unwrap().size(), 1);
+    let memory = memory.into_table().err().unwrap();
+    assert_eq!(memory.into_memory().ok().unwrap().size(), 1);
+    let adapter = adapter.into_func().err().unwrap();
+    assert!(adapter.into_adapter().is_ok());
+    Ok(())
+}
@@ -1,7 +1,64 @@
 use anyhow::Result;
+use std::future::Future;
+use std::pin::Pin;
 use std::rc::Rc;
 use std::sync::atomic::{AtomicUsize, Ordering::SeqCst};
-use wasmtime::{Callable, Func, FuncType, Instance, Module, Store, Trap, Val, ValType};
+use std::task::{Context, Poll};
+use wasmtime::{
+    AdapterFunc, AdapterType, AsyncCallable, Callable, CallableOwned, Config, Engine, Func,
+    FuncType, Instance, Module, OwnedCallable, Store, Trap, Val, ValRef, ValType,
+};
+
+/// A host function which does nothing.
+struct Nop;
+
+impl Callable for Nop {
+    fn call(&self, _params: &[Val], _results: &mut [Val]) -> Result<(), Trap> {
+        Ok(())
+    }
+}
+
+/// A host function which doubles its `i32` parameter. Called asynchronously it
+/// yields once before producing its result.
+struct Double;
+
+impl Callable for Double {
+    fn call(&self, params: &[Val], results: &mut [Val]) -> Result<(), Trap> {
+        results[0] = Val::I32(params[0].unwrap_i32() * 2);
+        Ok(())
+    }
+}
+
+impl AsyncCallable for Double {
+    fn call<'a>(
+        &'a self,
+        params: &'a [Val],
+        results: &'a mut [Val],
+    ) -> Pin<Box<dyn Future<Output = Result<(), Trap>> + 'a>> {
+        Box::pin(async move {
+            let x = async { params[0].unwrap_i32() }.await;
+            YieldOnce(false).await;
+            results[0] = Val::I32(x * 2);
+            Ok(())
+        })
+    }
+}
+
+/// A future which is pending the first time it's polled.
+struct YieldOnce(bool);
+
+impl Future for YieldOnce {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        if self.0 {
+            return Poll::Ready(());
+        }
+        self.0 = true;
+        cx.waker().wake_by_ref();
+        Poll::Pending
+    }
+}
 
 #[test]
 fn func_constructors() {
@@ -109,6 +166,33 @@ fn signatures_match() {
     assert_eq!(f.ty().results(), &[ValType::F64]);
 }
 
+#[test]
+fn wrap_infers_signature() -> Result<()> {
+    let store = Store::default();
+    Func::wrap(&store, || {});
+    Func::wrap(&store, |_: f32, _: f64| -> Result<i64, Trap> { loop {} });
+
+    let add = Func::wrap(&store, |a: i32, b: i32| a + b);
+    assert_eq!(add.ty().params(), &[ValType::I32, ValType::I32]);
+    assert_eq!(add.ty().results(), &[ValType::I32]);
+
+    let module = Module::new(
+        &store,
+        r#"
+            (module
+                (import "" "add" (func $add (param i32 i32) (result i32)))
+                (func (export "run") (result i32)
+                    i32.const 40
+                    i32.const 2
+                    call $add))
+        "#,
+    )?;
+    let instance = Instance::new(&module, &[add.into()])?;
+    let run = instance.get_func("run").unwrap().get0::<i32>()?;
+    assert_eq!(run()?, 42);
+    Ok(())
+}
+
 #[test]
 fn import_works() -> Result<()> {
     static HITS: AtomicUsize = AtomicUsize::new(0);
@@ -327,3 +411,733 @@ fn call_wrapped_func() -> Result<()> {
     assert_eq!(f.get0::<f64>()?()?, 4.0);
     Ok(())
 }
+
+#[test]
+fn native_address() -> Result<()> {
+    let store = Store::default();
+    let module = Module::new(&store, r#"(module (func (export "f")))"#)?;
+    let instance = Instance::new(&module, &[])?;
+    let f = instance.get_export("f").unwrap().func().unwrap();
+    assert!(f.native_address().is_some());
+
+    let ty = FuncType::new(Box::new([]), Box::new([]));
+    let host = Func::noop(&store, ty);
+    assert!(host.native_address().is_none());
+    Ok(())
+}
+
+#[test]
+fn lowered_adapter_interface_ty() -> Result<()> {
+    let store = Store::default();
+    let adapter_ty = AdapterType::new(Box::new([ValType::I32]), Box::new([ValType::I32]));
+    let adapter = AdapterFunc::new(&store, adapter_ty.clone(), Rc::new(Double));
+    let f = adapter.lower(FuncType::new(
+        Box::new([ValType::I32]),
+        Box::new([ValType::I32]),
+    ));
+    assert_eq!(f.interface_ty(), Some(&adapter_ty));
+    assert_eq!(f.call(&[Val::I32(4)])?[0].unwrap_i32(), 8);
+
+    let core = Func::wrap0(&store, || {});
+    assert!(core.interface_ty().is_none());
+    Ok(())
+}
+
+#[test]
+fn func_type_builder() {
+    let explicit = FuncType::new(
+        Box::new([ValType::I32, ValType::F64]),
+        Box::new([ValType::I64]),
+    );
+    let built = FuncType::builder()
+        .param(ValType::I32)
+        .param(ValType::F64)
+        .result(ValType::I64)
+        .build();
+    assert_eq!(built, explicit);
+    assert_eq!(
+        FuncType::builder().build(),
+        FuncType::new(Box::new([]), Box::new([]))
+    );
+}
+
+#[test]
+fn func_type_from_vecs() {
+    let ty = FuncType::from_vecs(vec![ValType::I32, ValType::F64], vec![ValType::I64]);
+    assert_eq!(ty.params(), &[ValType::I32, ValType::F64]);
+    assert_eq!(ty.results(), &[ValType::I64]);
+    assert_eq!(
+        ty,
+        FuncType::new(
+            Box::new([ValType::I32, ValType::F64]),
+            Box::new([ValType::I64]),
+        )
+    );
+    assert_eq!(
+        FuncType::from_vecs(Vec::new(), Vec::new()),
+        FuncType::new(Box::new([]), Box::new([]))
+    );
+}
+
+#[test]
+fn func_type_hash() {
+    use std::collections::hash_map::DefaultHasher;
+    use std::collections::HashMap;
+    use std::hash::{Hash, Hasher};
+
+    fn hash(ty: &FuncType) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        ty.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    let a = FuncType::from_vecs(vec![ValType::I32, ValType::F64], vec![ValType::I64]);
+    let b = FuncType::builder()
+        .param(ValType::I32)
+        .param(ValType::F64)
+        .result(ValType::I64)
+        .build();
+    assert_eq!(a, b);
+    assert_eq!(hash(&a), hash(&b));
+
+    // Parameter order, and which side of the signature a type is on, both
+    // matter.
+    let swapped = FuncType::from_vecs(vec![ValType::F64, ValType::I32], vec![ValType::I64]);
+    let shifted = FuncType::from_vecs(vec![ValType::I32], vec![ValType::F64, ValType::I64]);
+    assert_ne!(a, swapped);
+    assert_ne!(a, shifted);
+    assert_ne!(hash(&a), hash(&swapped));
+    assert_ne!(hash(&a), hash(&shifted));
+
+    let mut map = HashMap::new();
+    map.insert(a, 1);
+    map.insert(swapped, 2);
+    map.insert(shifted, 3);
+    assert_eq!(map.len(), 3);
+    assert_eq!(map[&b], 1);
+}
+
+#[test]
+fn caller_memory() -> Result<()> {
+    use std::cell::RefCell;
+    use wasmtime::Caller;
+
+    struct Log(RefCell<Vec<String>>);
+
+    impl Callable for Log {
+        fn call(&self, _params: &[Val], _results: &mut [Val]) -> Result<(), Trap> {
+            Err(Trap::new("`log` needs to be called from wasm"))
+        }
+
+        fn call_with_caller(
+            &self,
+            caller: Caller<'_>,
+            params: &[Val],
+            _results: &mut [Val],
+        ) -> Result<(), Trap> {
+            let memory = match caller
+                .get_export("memory")
+                .and_then(|e| e.memory().cloned())
+            {
+                Some(memory) => memory,
+                None => return Err(Trap::new("missing `memory` export")),
+            };
+            let ptr = params[0].unwrap_i32() as usize;
+            let len = params[1].unwrap_i32() as usize;
+            let bytes = unsafe { &memory.data_unchecked()[ptr..ptr + len] };
+            let s = std::str::from_utf8(bytes).map_err(|_| Trap::new("invalid utf-8"))?;
+            self.0.borrow_mut().push(s.to_string());
+            Ok(())
+        }
+    }
+
+    let store = Store::default();
+    let module = Module::new(
+        &store,
+        r#"
+            (module
+                (import "" "log" (func $log (param i32 i32)))
+                (memory (export "memory") 1)
+                (data (i32.const 8) "hello")
+                (func (export "run")
+                    i32.const 8
+                    i32.const 5
+                    call $log))
+        "#,
+    )?;
+    let log = Rc::new(Log(RefCell::new(Vec::new())));
+    let ty = FuncType::new(Box::new([ValType::I32, ValType::I32]), Box::new([]));
+    let func = Func::new(&store, ty, log.clone());
+    let instance = Instance::new(&module, &[func.clone().into()])?;
+    instance
+        .get_export("run")
+        .unwrap()
+        .func()
+        .unwrap()
+        .call(&[])?;
+    assert_eq!(*log.0.borrow(), ["hello"]);
+
+    // calling directly from the host has no caller to look at
+    assert!(func.call(&[Val::I32(8), Val::I32(5)]).is_err());
+    Ok(())
+}
+
+#[test]
+fn call_batch() -> Result<()> {
+    struct Add;
+
+    impl Callable for Add {
+        fn call(&self, params: &[Val], results: &mut [Val]) -> Result<(), Trap> {
+            results[0] = (params[0].unwrap_i32() + params[1].unwrap_i32()).into();
+            Ok(())
+        }
+    }
+
+    let store = Store::default();
+    let ty = FuncType::from_vecs(vec![ValType::I32, ValType::I32], vec![ValType::I32]);
+    let f = Func::new(&store, ty, Rc::new(Add));
+
+    let params = [
+        [Val::I32(1), Val::I32(2)],
+        [Val::I32(3), Val::I32(4)],
+        [Val::I32(-5), Val::I32(5)],
+    ];
+    let mut results = [[Val::I32(0)], [Val::I32(0)], [Val::I32(0)]];
+    {
+        let mut batches = params
+            .iter()
+            .zip(results.iter_mut())
+            .map(|(p, r)| (&p[..], &mut r[..]))
+            .collect::<Vec<_>>();
+        f.call_batch(&mut batches)?;
+    }
+    for (p, r) in params.iter().zip(results.iter()) {
+        let expected = f.call(p)?;
+        assert_eq!(r[0].unwrap_i32(), expected[0].unwrap_i32());
+    }
+
+    let mut bad_results = [Val::I32(0), Val::I32(0)];
+    let mut batches = [(&params[0][..], &mut bad_results[..])];
+    assert!(f.call_batch(&mut batches).is_err());
+    Ok(())
+}
+
+#[test]
+fn call_unchecked() -> Result<()> {
+    let store = Store::default();
+    let module = Module::new(
+        &store,
+        r#"
+            (module
+                (func (export "f") (param i32 i64 f32) (result i64)
+                    local.get 1
+                    local.get 0
+                    i64.extend_i32_s
+                    i64.mul
+                    local.get 2
+                    i64.trunc_f32_s
+                    i64.add))
+        "#,
+    )?;
+    let instance = Instance::new(&module, &[])?;
+    let f = instance.exports()[0].func().unwrap();
+
+    for (a, b, c) in &[(1, 2, 3.5f32), (-7, 6, -1.0), (0, i64::max_value(), 0.0)] {
+        let params = [Val::I32(*a), Val::I64(*b), Val::F32(c.to_bits())];
+        let checked = f.call(&params)?;
+        let unchecked = unsafe { f.call_unchecked(&params)? };
+        assert_eq!(checked.len(), unchecked.len());
+        assert_eq!(checked[0].unwrap_i64(), unchecked[0].unwrap_i64());
+    }
+    Ok(())
+}
+
+#[test]
+fn call_returns_v128() -> Result<()> {
+    let mut config = Config::new();
+    config.wasm_simd(true).wasm_multi_value(true);
+    let store = Store::new(&Engine::new(&config));
+    let module = Module::new(
+        &store,
+        r#"
+            (module
+                (func (export "f") (param v128) (result i32 v128 i64)
+                    i32.const 1
+                    local.get 0
+                    v128.const i32x4 1 2 3 4
+                    i32x4.add
+                    i64.const 2))
+        "#,
+    )?;
+    let instance = Instance::new(&module, &[])?;
+    let f = instance.exports()[0].func().unwrap();
+
+    let results = f.call(&[Val::V128(0x00000001_00000001_00000001_00000001)])?;
+    assert_eq!(results.len(), 3);
+    assert_eq!(results[0].unwrap_i32(), 1);
+    match results[1] {
+        Val::V128(v) => assert_eq!(v, 0x00000005_00000004_00000003_00000002),
+        ref other => panic!("expected v128, got {:?}", other),
+    }
+    assert_eq!(results[2].unwrap_i64(), 2);
+    Ok(())
+}
+
+#[test]
+fn adapter_core_func() -> Result<()> {
+    let mut config = Config::new();
+    config.wasm_interface_types(true).wasm_reference_types(true);
+    let store = Store::new(&Engine::new(&config));
+    let module = Module::from_file(&store, "../../examples/string-to-memory.wasm")?;
+    let instance = Instance::new(&module, &[])?;
+    let set = instance.get_export("set").unwrap().adapter().unwrap();
+    let get = instance.get_export("get").unwrap().adapter().unwrap();
+
+    // The adapter path marshals the string into memory, which the core `get`
+    // function then reports as a raw pointer and length.
+    set.call(&[Val::String("Hello world".to_string())])?;
+    assert_eq!(get.call(&[])?[0].unwrap_string(), "Hello world");
+    let raw = get.core_func().unwrap().call(&[])?;
+    let ptr = raw[0].unwrap_i32();
+    assert_eq!(raw[1].unwrap_i32(), 11);
+
+    // Calling the core `set` function with an encoded pointer and length is
+    // equivalent to passing the adapter the string those bytes hold.
+    let core_set = set.core_func().unwrap();
+    assert_eq!(core_set.ty().params(), &[ValType::I32, ValType::I32]);
+    core_set.call(&[Val::I32(ptr), Val::I32(5)])?;
+    assert_eq!(get.call(&[])?[0].unwrap_string(), "Hello");
+
+    // Host adapters don't wrap a core function.
+    let ty = AdapterType::new(Box::new([]), Box::new([]));
+    let host = AdapterFunc::new(&store, ty, Rc::new(Nop));
+    assert!(host.core_func().is_none());
+    Ok(())
+}
+
+#[test]
+fn adapter_call_ref() -> Result<()> {
+    let mut config = Config::new();
+    config.wasm_interface_types(true).wasm_reference_types(true);
+    let store = Store::new(&Engine::new(&config));
+    let module = Module::from_file(&store, "../../examples/string-to-memory.wasm")?;
+    let instance = Instance::new(&module, &[])?;
+    let set = instance.get_export("set").unwrap().adapter().unwrap();
+    let get = instance.get_export("get").unwrap().adapter().unwrap();
+
+    // Borrowed strings round-trip just like owned ones.
+    set.call(&[Val::String("Hello world".to_string())])?;
+    let owned = get.call(&[])?;
+    set.call_ref(&[ValRef::Str("Hello world")])?;
+    let borrowed = get.call_ref(&[])?;
+    assert_eq!(owned[0].unwrap_string(), borrowed[0].unwrap_string());
+
+    let hello = Val::String("Hello".to_string());
+    set.call_ref(&[ValRef::Val(&hello)])?;
+    assert_eq!(get.call(&[])?[0].unwrap_string(), "Hello");
+
+    // Host adapters receive owned copies of borrowed arguments.
+    struct Echo;
+
+    impl Callable for Echo {
+        fn call(&self, params: &[Val], results: &mut [Val]) -> Result<(), Trap> {
+            results[0] = params[0].clone();
+            Ok(())
+        }
+    }
+
+    let ty = AdapterType::new(Box::new([ValType::String]), Box::new([ValType::String]));
+    let echo = AdapterFunc::new(&store, ty, Rc::new(Echo));
+    let results = echo.call_ref(&[ValRef::from("hi")])?;
+    assert_eq!(results[0].unwrap_string(), "hi");
+    Ok(())
+}
+
+#[test]
+fn adapter_call_typed() -> Result<()> {
+    let mut config = Config::new();
+    config.wasm_interface_types(true).wasm_reference_types(true);
+    let store = Store::new(&Engine::new(&config));
+    let module = Module::from_file(&store, "../../examples/string-to-memory.wasm")?;
+    let instance = Instance::new(&module, &[])?;
+    let set = instance.get_export("set").unwrap().adapter().unwrap();
+    let get = instance.get_export("get").unwrap().adapter().unwrap();
+
+    assert!(set.call_typed(&[Val::from("typed")])?.is_empty());
+    let results = get.call_typed(&[])?;
+    let tys = results.iter().map(|(ty, _)| ty.clone()).collect::<Vec<_>>();
+    assert_eq!(tys, get.ty().results());
+    assert_eq!(results[0].1.unwrap_string(), "typed");
+
+    // Host adapters which return values of the wrong type are caught.
+    struct Liar;
+
+    impl Callable for Liar {
+        fn call(&self, _params: &[Val], results: &mut [Val]) -> Result<(), Trap> {
+            results[0] = Val::I32(1);
+            Ok(())
+        }
+    }
+
+    let ty = AdapterType::new(Box::new([]), Box::new([ValType::String]));
+    let liar = AdapterFunc::new(&store, ty, Rc::new(Liar));
+    let trap = liar.call_typed(&[]).unwrap_err();
+    assert_eq!(
+        trap.message(),
+        "adapter result 0 has type i32, but string was declared"
+    );
+    Ok(())
+}
+
+#[test]
+fn adapter_call_typed_out() -> Result<()> {
+    let mut config = Config::new();
+    config.wasm_interface_types(true).wasm_reference_types(true);
+    let store = Store::new(&Engine::new(&config));
+    let module = Module::from_file(&store, "../../examples/string-to-memory.wasm")?;
+    let instance = Instance::new(&module, &[])?;
+    let set = instance.get_adapter("set").unwrap();
+    let get = instance.get_adapter("get").unwrap();
+
+    let () = set.call_typed_out(&[Val::from("lifted")])?;
+    let (s,) = get.call_typed_out::<(String,)>(&[])?;
+    assert_eq!(s, "lifted");
+
+    let err = get.call_typed_out::<(String, i32)>(&[]).unwrap_err();
+    assert_eq!(err.to_string(), "expected 2 results, adapter produced 1");
+    let err = get.call_typed_out::<(i32,)>(&[]).unwrap_err();
+    assert_eq!(err.to_string(), "failed to lift adapter result 0");
+    assert_eq!(
+        err.root_cause().to_string(),
+        "expected i32 value, found string"
+    );
+    Ok(())
+}
+
+#[test]
+fn arity() -> Result<()> {
+    let store = Store::default();
+    let module = Module::new(
+        &store,
+        r#"
+            (module
+                (func (export "add") (param i32 i32) (result i32)
+                    local.get 0
+                    local.get 1
+                    i32.add))
+        "#,
+    )?;
+    let instance = Instance::new(&module, &[])?;
+    let add = instance.exports()[0].func().unwrap();
+    assert_eq!(add.param_arity(), 2);
+    assert_eq!(add.result_arity(), 1);
+
+    let f = Func::wrap2(&store, |a: i32, b: i32| a + b);
+    assert_eq!(f.param_arity(), 2);
+    assert_eq!(f.result_arity(), 1);
+    Ok(())
+}
+
+#[test]
+fn new_boxed() -> Result<()> {
+    struct Counter(std::cell::Cell<i32>);
+
+    impl Callable for Counter {
+        fn call(&self, params: &[Val], results: &mut [Val]) -> Result<(), Trap> {
+            self.0.set(self.0.get() + params[0].unwrap_i32());
+            results[0] = self.0.get().into();
+            Ok(())
+        }
+    }
+
+    let store = Store::default();
+    let ty = FuncType::from_vecs(vec![ValType::I32], vec![ValType::I32]);
+    let f = Func::new_boxed(&store, ty, Box::new(Counter(std::cell::Cell::new(0))));
+    assert_eq!(f.call(&[Val::I32(2)])?[0].unwrap_i32(), 2);
+    assert_eq!(f.call(&[Val::I32(3)])?[0].unwrap_i32(), 5);
+
+    let module = Module::new(
+        &store,
+        r#"
+            (module
+                (import "" "" (func $counter (param i32) (result i32)))
+                (func (export "run") (result i32)
+                    i32.const 10
+                    call $counter))
+        "#,
+    )?;
+    let instance = Instance::new(&module, &[f.into()])?;
+    let run = instance.exports()[0].func().unwrap();
+    assert_eq!(run.call(&[])?[0].unwrap_i32(), 15);
+    Ok(())
+}
+
+#[test]
+fn callable_owned() -> Result<()> {
+    struct Swap;
+
+    impl CallableOwned for Swap {
+        fn call(&self, params: &[Val]) -> Result<Vec<Val>, Trap> {
+            Ok(params.iter().rev().cloned().collect())
+        }
+    }
+
+    struct Wrong;
+
+    impl CallableOwned for Wrong {
+        fn call(&self, _params: &[Val]) -> Result<Vec<Val>, Trap> {
+            Ok(vec![Val::I64(0)])
+        }
+    }
+
+    let store = Store::default();
+    let ty = FuncType::from_vecs(
+        vec![ValType::I32, ValType::I64],
+        vec![ValType::I64, ValType::I32],
+    );
+    let f = Func::new(&store, ty.clone(), Rc::new(OwnedCallable::new(ty, Swap)));
+    let results = f.call(&[Val::I32(1), Val::I64(2)])?;
+    assert_eq!(results[0].unwrap_i64(), 2);
+    assert_eq!(results[1].unwrap_i32(), 1);
+
+    // Returning the wrong number of results traps.
+    let ty = FuncType::from_vecs(vec![ValType::I32, ValType::I64], vec![ValType::I32]);
+    let f = Func::new(&store, ty.clone(), Rc::new(OwnedCallable::new(ty, Swap)));
+    let trap = f.call(&[Val::I32(1), Val::I64(2)]).err().unwrap();
+    assert_eq!(trap.message(), "expected 1 results, got 2");
+
+    // As does returning results of the wrong type.
+    let ty = FuncType::from_vecs(vec![], vec![ValType::I32]);
+    let f = Func::new(&store, ty.clone(), Rc::new(OwnedCallable::new(ty, Wrong)));
+    let trap = f.call(&[]).err().unwrap();
+    assert_eq!(trap.message(), "expected result of type i32, got i64");
+    Ok(())
+}
+
+#[test]
+fn new_mut() -> Result<()> {
+    let store = Store::default();
+    let module = Module::new(
+        &store,
+        r#"
+            (module
+                (import "" "" (func $host (result i32)))
+                (func (export "run") (result i32)
+                    call $host))
+        "#,
+    )?;
+
+    // State captured by the closure persists across calls from wasm.
+    let mut count = 0;
+    let ty = FuncType::from_vecs(vec![], vec![ValType::I32]);
+    let counter = Func::new_mut(&store, ty.clone(), move |_params, results| {
+        count += 1;
+        results[0] = Val::I32(count);
+        Ok(())
+    });
+    let instance = Instance::new(&module, &[counter.into()])?;
+    let run = instance.exports()[0].func().unwrap();
+    for i in 1..=3 {
+        assert_eq!(run.call(&[])?[0].unwrap_i32(), i);
+    }
+
+    // Calling back into the closure while it's running traps.
+    let run_slot = Rc::new(std::cell::RefCell::new(None::<Func>));
+    let slot = run_slot.clone();
+    let reentrant = Func::new_mut(&store, ty, move |_params, results| {
+        let run = slot.borrow().clone().unwrap();
+        let trap = run.call(&[]).err().expect("reentrant call should trap");
+        assert!(trap.message().contains("reentrantly"), "{}", trap.message());
+        results[0] = Val::I32(0);
+        Ok(())
+    });
+    let instance = Instance::new(&module, &[reentrant.into()])?;
+    let run = instance.exports()[0].func().unwrap().clone();
+    *run_slot.borrow_mut() = Some(run.clone());
+    assert_eq!(run.call(&[])?[0].unwrap_i32(), 0);
+    run_slot.borrow_mut().take();
+    Ok(())
+}
+
+#[test]
+fn clone_into() -> Result<()> {
+    let store1 = Store::default();
+    let store2 = Store::default();
+    let ty = FuncType::from_vecs(vec![ValType::I32], vec![ValType::I32]);
+    let f = Func::new(&store1, ty, Rc::new(Double));
+
+    let g = f.clone_into(&store2).unwrap();
+    assert!(Store::same(g.store(), &store2));
+    assert_eq!(g.ty(), f.ty());
+    assert_eq!(g.call(&[Val::I32(21)])?[0].unwrap_i32(), 42);
+
+    let module = Module::new(
+        &store2,
+        r#"
+            (module
+                (import "" "" (func $double (param i32) (result i32)))
+                (func (export "run") (result i32)
+                    i32.const 4
+                    call $double))
+        "#,
+    )?;
+    let instance = Instance::new(&module, &[g.into()])?;
+    let run = instance.exports()[0].func().unwrap();
+    assert_eq!(run.call(&[])?[0].unwrap_i32(), 8);
+
+    // Functions defined in wasm can't be moved to another store.
+    assert!(run.clone_into(&store1).is_none());
+    Ok(())
+}
+
+#[test]
+fn noop_and_identity() -> Result<()> {
+    let store = Store::default();
+
+    let ty = FuncType::from_vecs(
+        vec![ValType::I32],
+        vec![ValType::I64, ValType::F32, ValType::AnyRef],
+    );
+    let noop = Func::noop(&store, ty);
+    let results = noop.call(&[Val::I32(7)])?;
+    assert_eq!(results.len(), 3);
+    assert_eq!(results[0].unwrap_i64(), 0);
+    assert_eq!(results[1].unwrap_f32(), 0.0);
+    assert!(results[2].is_null());
+
+    let ty = FuncType::from_vecs(
+        vec![ValType::I32, ValType::F64],
+        vec![ValType::I32, ValType::F64],
+    );
+    let identity = Func::identity(&store, ty)?;
+    let results = identity.call(&[Val::I32(1), Val::F64(2.5f64.to_bits())])?;
+    assert_eq!(results[0].unwrap_i32(), 1);
+    assert_eq!(results[1].unwrap_f64(), 2.5);
+
+    let ty = FuncType::from_vecs(vec![ValType::I32], vec![ValType::I32]);
+    let identity = Func::identity(&store, ty)?;
+    let module = Module::new(
+        &store,
+        r#"
+            (module
+                (import "" "" (func $id (param i32) (result i32)))
+                (func (export "run") (result i32)
+                    i32.const 42
+                    call $id))
+        "#,
+    )?;
+    let instance = Instance::new(&module, &[identity.into()])?;
+    let run = instance.exports()[0].func().unwrap();
+    assert_eq!(run.call(&[])?[0].unwrap_i32(), 42);
+
+    let ty = FuncType::from_vecs(vec![ValType::I32], vec![ValType::I64]);
+    assert!(Func::identity(&store, ty).is_err());
+    Ok(())
+}
+
+#[test]
+fn call_returning_cstr() -> Result<()> {
+    let store = Store::default();
+    let module = Module::new(
+        &store,
+        r#"
+            (module
+                (memory (export "memory") 1)
+                (data (i32.const 8) "hello\00")
+                (data (i32.const 16) "\ff\fe\00")
+                (data (i32.const 65530) "open")
+                (func (export "get") (param i32) (result i32)
+                    local.get 0)
+                (func (export "none")))
+        "#,
+    )?;
+    let instance = Instance::new(&module, &[])?;
+    let memory = instance.get_export("memory").unwrap().memory().unwrap();
+    let get = instance.get_export("get").unwrap().func().unwrap();
+
+    assert_eq!(get.call_returning_cstr(&[Val::I32(8)], memory)?, "hello");
+    assert_eq!(get.call_returning_cstr(&[Val::I32(11)], memory)?, "lo");
+    assert_eq!(get.call_returning_cstr(&[Val::I32(0)], memory)?, "");
+
+    let err = get
+        .call_returning_cstr(&[Val::I32(16)], memory)
+        .unwrap_err();
+    assert!(err.to_string().contains("not valid UTF-8"), "{}", err);
+    let err = get
+        .call_returning_cstr(&[Val::I32(65530)], memory)
+        .unwrap_err();
+    assert!(err.to_string().contains("not NUL-terminated"), "{}", err);
+    let err = get
+        .call_returning_cstr(&[Val::I32(-1)], memory)
+        .unwrap_err();
+    assert!(err.to_string().contains("out of bounds"), "{}", err);
+
+    let none = instance.get_export("none").unwrap().func().unwrap();
+    assert!(none.call_returning_cstr(&[], memory).is_err());
+    Ok(())
+}
+
+#[test]
+fn new_async() -> Result<()> {
+    let store = Store::default();
+    let ty = FuncType::from_vecs(vec![ValType::I32], vec![ValType::I32]);
+    let double = Func::new_async(&store, ty, Rc::new(Double));
+    assert_eq!(double.call(&[Val::I32(21)])?[0].unwrap_i32(), 42);
+
+    // The function can also be called from wasm.
+    let module = Module::new(
+        &store,
+        r#"
+            (module
+                (import "" "double" (func $double (param i32) (result i32)))
+                (func (export "run") (param i32) (result i32)
+                    local.get 0
+                    call $double))
+        "#,
+    )?;
+    let instance = Instance::new(&module, &[double.into()])?;
+    let run = instance.exports()[0].func().unwrap();
+    assert_eq!(run.call(&[Val::I32(5)])?[0].unwrap_i32(), 10);
+    Ok(())
+}
+
+#[test]
+fn call_into() -> Result<()> {
+    let store = Store::default();
+    let module = Module::new(
+        &store,
+        r#"
+            (module
+                (func (export "add") (param i32 i32) (result i32)
+                    local.get 0
+                    local.get 1
+                    i32.add)
+                (func (export "none")))
+        "#,
+    )?;
+    let instance = Instance::new(&module, &[])?;
+    let add = instance.get_export("add").unwrap().func().unwrap();
+
+    // Extra entries are truncated and existing ones overwritten.
+    let mut results = vec![Val::I64(-1), Val::I64(-1)];
+    for i in 0..5 {
+        add.call_into(&[Val::I32(i), Val::I32(10)], &mut results)?;
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].unwrap_i32(), i + 10);
+    }
+
+    let none = instance.get_export("none").unwrap().func().unwrap();
+    none.call_into(&[], &mut results)?;
+    assert!(results.is_empty());
+    Ok(())
+}
+
+#[test]
+fn call_iter() -> Result<()> {
+    let store = Store::default();
+    let sub = Func::wrap2(&store, |a: i32, b: i32| a - b);
+    let results = sub.call_iter([10, 3].iter().map(|i| Val::I32(*i)))?;
+    assert_eq!(results[0].unwrap_i32(), 7);
+    assert!(sub.call_iter(std::iter::once(Val::I32(1))).is_err());
+    Ok(())
+}
@@ -91,3 +91,295 @@ fn use_after_drop() -> anyhow::Result<()> {
     assert_eq!(g.get().i32(), Some(101));
     Ok(())
 }
+
+#[test]
+fn modify() -> anyhow::Result<()> {
+    let store = Store::default();
+    let g = Global::new(
+        &store,
+        GlobalType::new(ValType::I64, Mutability::Var),
+        41i64.into(),
+    )?;
+    let new = g.modify(|v| (v.unwrap_i64() + 1).into())?;
+    assert_eq!(new.i64(), Some(42));
+    assert_eq!(g.get().i64(), Some(42));
+
+    // the result must match the global's type
+    assert!(g.modify(|_| 0i32.into()).is_err());
+    assert_eq!(g.get().i64(), Some(42));
+
+    // immutable globals can't be modified
+    let g = Global::new(
+        &store,
+        GlobalType::new(ValType::I64, Mutability::Const),
+        0i64.into(),
+    )?;
+    assert!(g.modify(|v| v).is_err());
+
+    // v128 globals exported from wasm can be modified too
+    let mut config = Config::new();
+    config.wasm_simd(true);
+    let store = Store::new(&Engine::new(&config));
+    let module = Module::new(
+        &store,
+        r#"(module (global (export "g") (mut v128) (v128.const i64x2 1 0)))"#,
+    )?;
+    let instance = Instance::new(&module, &[])?;
+    let g = instance.exports()[0].global().unwrap();
+    let new = g.modify(|v| Val::V128(v.unwrap_v128() + 1))?;
+    assert_eq!(new.v128(), Some(2));
+    assert_eq!(g.get().v128(), Some(2));
+    Ok(())
+}
+
+#[test]
+fn funcref() -> anyhow::Result<()> {
+    let store = Store::default();
+    let f = Func::wrap0(&store, || 1i32);
+    let g = Global::new(
+        &store,
+        GlobalType::new(ValType::FuncRef, Mutability::Var),
+        f.into(),
+    )?;
+    let f = g.get();
+    assert_eq!(f.unwrap_funcref().call(&[])?[0].unwrap_i32(), 1);
+
+    g.set(Func::wrap0(&store, || 2i32).into())?;
+    let f = g.get();
+    assert_eq!(f.unwrap_funcref().call(&[])?[0].unwrap_i32(), 2);
+    Ok(())
+}
+
+#[test]
+fn funcref_imported_into_wasm() -> anyhow::Result<()> {
+    let mut config = Config::new();
+    config.wasm_reference_types(true);
+    let store = Store::new(&Engine::new(&config));
+    let module = Module::new(
+        &store,
+        r#"
+            (module
+                (import "" "g" (global $g (mut funcref)))
+                (global $copy (export "copy") (mut funcref) (ref.null))
+                (export "g" (global $g))
+                (func (export "is_null") (result i32)
+                    global.get $g
+                    ref.is_null)
+                (func (export "copy_g")
+                    global.get $g
+                    global.set $copy))
+        "#,
+    )?;
+    let g = Global::new(
+        &store,
+        GlobalType::new(ValType::FuncRef, Mutability::Var),
+        Func::wrap0(&store, || 1i32).into(),
+    )?;
+    let instance = Instance::new(&module, &[g.clone().into()])?;
+    let copy = instance.get_export("copy").unwrap().global().unwrap();
+    let reexported = instance.get_export("g").unwrap().global().unwrap();
+    let is_null = instance.get_export("is_null").unwrap().func().unwrap();
+    let copy_g = instance.get_export("copy_g").unwrap().func().unwrap();
+
+    // wasm sees the reference stored by the host...
+    assert_eq!(is_null.call(&[])?[0].unwrap_i32(), 0);
+    assert_eq!(*reexported.ty().content(), ValType::FuncRef);
+    let f = reexported.get();
+    assert_eq!(f.unwrap_funcref().call(&[])?[0].unwrap_i32(), 1);
+
+    // ...and the host sees references stored by wasm.
+    assert!(copy.get().funcref().is_none());
+    copy_g.call(&[])?;
+    let f = copy.get();
+    assert_eq!(f.unwrap_funcref().call(&[])?[0].unwrap_i32(), 1);
+
+    // The copy stays usable after the host overwrites the original.
+    g.set(Func::wrap0(&store, || 2i32).into())?;
+    assert_eq!(g.get().unwrap_funcref().call(&[])?[0].unwrap_i32(), 2);
+    let f = copy.get();
+    assert_eq!(f.unwrap_funcref().call(&[])?[0].unwrap_i32(), 1);
+    Ok(())
+}
+
+#[test]
+fn typed() -> anyhow::Result<()> {
+    let store = Store::default();
+    let g = Global::new(
+        &store,
+        GlobalType::new(ValType::I64, Mutability::Var),
+        Val::I64(0),
+    )?;
+    assert!(g.typed::<i32>().is_err());
+    assert!(g.typed::<f64>().is_err());
+    assert!(g.typed::<()>().is_err());
+
+    let counter = g.typed::<i64>()?;
+    for _ in 0..100 {
+        counter.set(counter.get() + 3)?;
+    }
+    assert_eq!(counter.get(), 300);
+    assert_eq!(g.get().i64(), Some(300));
+
+    counter.set(-1)?;
+    assert_eq!(g.get().i64(), Some(-1));
+
+    let g = Global::new(
+        &store,
+        GlobalType::new(ValType::F32, Mutability::Const),
+        1.5f32.into(),
+    )?;
+    let typed = g.typed::<f32>()?;
+    assert_eq!(typed.get(), 1.5);
+    assert!(typed.set(2.0).is_err());
+    assert_eq!(typed.get(), 1.5);
+    Ok(())
+}
+
+#[test]
+fn string() -> anyhow::Result<()> {
+    let store = Store::default();
+    let g = Global::new(
+        &store,
+        GlobalType::new(ValType::String, Mutability::Var),
+        Val::String("hello".to_string()),
+    )?;
+    assert_eq!(g.get().unwrap_string(), "hello");
+
+    g.set(Val::String("a longer string than before".to_string()))?;
+    assert_eq!(g.get().unwrap_string(), "a longer string than before");
+    assert_eq!(
+        g.clone().get().unwrap_string(),
+        "a longer string than before"
+    );
+    assert!(g.set(Val::I32(0)).is_err());
+
+    let g = Global::new(
+        &store,
+        GlobalType::new(ValType::String, Mutability::Const),
+        Val::String(String::new()),
+    )?;
+    assert_eq!(g.get().unwrap_string(), "");
+    assert!(g.set(Val::String("x".to_string())).is_err());
+    Ok(())
+}
+
+#[test]
+fn nan_bit_eq() -> anyhow::Result<()> {
+    let store = Store::default();
+    let nan = Val::F64(0x7ff8_0000_0000_1234);
+    let a = Global::new(
+        &store,
+        GlobalType::new(ValType::F64, Mutability::Const),
+        nan.clone(),
+    )?;
+    let b = Global::new(
+        &store,
+        GlobalType::new(ValType::F64, Mutability::Const),
+        nan,
+    )?;
+    assert!(a.get().bit_eq(&b.get()));
+    assert!(a.get().unwrap_f64() != b.get().unwrap_f64());
+
+    let other_nan = Val::F64(0x7ff8_0000_0000_0000);
+    assert!(!a.get().bit_eq(&other_nan));
+    assert!(!Val::F32(0).bit_eq(&Val::F32(0x8000_0000)));
+    assert!(!Val::I32(0).bit_eq(&Val::I64(0)));
+    Ok(())
+}
+
+#[test]
+fn v128_unsupported() {
+    let store = Store::default();
+    let err = Global::new(
+        &store,
+        GlobalType::new(ValType::V128, Mutability::Const),
+        Val::V128(0),
+    )
+    .err()
+    .expect("v128 globals should be rejected");
+    assert_eq!(err.to_string(), "v128 globals are not yet supported");
+}
+
+#[test]
+fn raw_u128() -> anyhow::Result<()> {
+    let mut config = Config::new();
+    config.wasm_simd(true);
+    let store = Store::new(&Engine::new(&config));
+    let module = Module::new(
+        &store,
+        r#"
+            (module
+                (global (export "g") (mut v128) (v128.const i64x2 0 0))
+                (global (export "c") v128 (v128.const i64x2 0 0)))
+        "#,
+    )?;
+    let instance = Instance::new(&module, &[])?;
+    let g = instance.exports()[0].global().unwrap();
+    assert_eq!(*g.ty().content(), ValType::V128);
+    assert_eq!(g.read_u128(), 0);
+
+    let bits = 0x0123_4567_89ab_cdef_fedc_ba98_7654_3210;
+    g.write_u128(bits)?;
+    assert_eq!(g.read_u128(), bits);
+
+    let c = instance.exports()[1].global().unwrap();
+    assert!(c.write_u128(bits).is_err());
+    assert_eq!(c.read_u128(), 0);
+    Ok(())
+}
+
+#[test]
+fn read_only() -> anyhow::Result<()> {
+    let store = Store::default();
+    let g = Global::new(
+        &store,
+        GlobalType::new(ValType::I32, Mutability::Var),
+        1.into(),
+    )?;
+    let view = g.read_only();
+    assert_eq!(view.ty().mutability(), Mutability::Var);
+    assert_eq!(view.get().i32(), Some(1));
+
+    // Writes through the original global are visible through the view, which
+    // itself has no `set` method.
+    g.set(2.into())?;
+    assert_eq!(view.get().i32(), Some(2));
+    assert_eq!(view.clone().get().i32(), Some(2));
+    Ok(())
+}
+
+#[test]
+fn from_bits() -> anyhow::Result<()> {
+    let store = Store::default();
+    let nan = 0x7ff4_0000_0000_0001;
+    let g = Global::from_bits(
+        &store,
+        GlobalType::new(ValType::F64, Mutability::Const),
+        nan,
+    )?;
+    assert_eq!(g.get().unwrap_f64().to_bits(), nan);
+    match g.get() {
+        Val::F64(bits) => assert_eq!(bits, nan),
+        _ => panic!("expected an f64"),
+    }
+
+    let ty = GlobalType::new(ValType::I32, Mutability::Const);
+    let g = Global::from_bits(&store, ty.clone(), 0xffff_ffff)?;
+    assert_eq!(g.get().unwrap_i32(), -1);
+    assert!(Global::from_bits(&store, ty, 1 << 32).is_err());
+    assert!(Global::from_bits(
+        &store,
+        GlobalType::new(ValType::AnyRef, Mutability::Const),
+        0
+    )
+    .is_err());
+
+    let ty = GlobalType::new(ValType::V128, Mutability::Var);
+    let g = Global::from_bits_u128(&store, ty, u128::max_value() - 1)?;
+    assert_eq!(g.read_u128(), u128::max_value() - 1);
+    assert_eq!(g.get().unwrap_v128(), u128::max_value() - 1);
+    g.set(Val::V128(7))?;
+    assert_eq!(g.get().unwrap_v128(), 7);
+    assert_eq!(g.read_u128(), 7);
+    Ok(())
+}
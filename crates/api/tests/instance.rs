@@ -0,0 +1,194 @@
+use anyhow::Result;
+use wasmtime::*;
+
+#[test]
+fn iter_exports() -> Result<()> {
+    let store = Store::default();
+    let module = Module::new(
+        &store,
+        r#"
+            (module
+                (func (export "f"))
+                (memory (export "m") 1)
+            )
+        "#,
+    )?;
+    let instance = Instance::new(&module, &[])?;
+
+    let names = instance
+        .iter_exports()
+        .map(|(name, _)| name)
+        .collect::<Vec<_>>();
+    assert_eq!(names, ["f", "m"]);
+
+    let mut exports = instance.iter_exports();
+    assert!(exports.next().unwrap().1.func().is_some());
+    assert!(exports.next().unwrap().1.memory().is_some());
+    assert!(exports.next().is_none());
+    Ok(())
+}
+
+#[test]
+fn sorted_exports() -> Result<()> {
+    let store = Store::default();
+    let module = Module::new(
+        &store,
+        r#"
+            (module
+                (func (export "zeta"))
+                (memory (export "alpha") 1)
+                (global (export "mu") i32 (i32.const 0))
+                (func (export "beta"))
+            )
+        "#,
+    )?;
+    let instance = Instance::new(&module, &[])?;
+
+    let exports = instance.sorted_exports();
+    let names = exports
+        .iter()
+        .map(|(name, _)| name.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(names, ["alpha", "beta", "mu", "zeta"]);
+    assert!(exports[0].1.memory().is_some());
+    assert!(exports[1].1.func().is_some());
+    assert!(exports[2].1.global().is_some());
+    Ok(())
+}
+
+#[test]
+fn mismatched_import_is_named() -> Result<()> {
+    let store = Store::default();
+    let module = Module::new(
+        &store,
+        r#"
+            (module
+                (import "env" "callback" (func)))
+        "#,
+    )?;
+    let global = Global::new(
+        &store,
+        GlobalType::new(ValType::I32, Mutability::Const),
+        Val::I32(0),
+    )?;
+    let err = Instance::new(&module, &[global.into()])
+        .err()
+        .expect("instantiation should fail");
+    let message = err.to_string();
+    assert!(message.contains("env::callback"), "{}", message);
+    assert!(message.contains("Func"), "{}", message);
+    assert!(message.contains("Global"), "{}", message);
+    Ok(())
+}
+
+#[test]
+fn typed_export_lookups() -> Result<()> {
+    let store = Store::default();
+    let module = Module::new(
+        &store,
+        r#"
+            (module
+                (memory (export "memory") 1)
+                (global (export "answer") i32 (i32.const 42))
+                (table (export "table") 2 funcref)
+                (func (export "f")))
+        "#,
+    )?;
+    let instance = Instance::new(&module, &[])?;
+
+    let memory = instance.get_memory("memory").unwrap();
+    assert_eq!(memory.size(), 1);
+    let global = instance.get_global("answer").unwrap();
+    assert_eq!(global.get().unwrap_i32(), 42);
+    assert_eq!(instance.get_table("table").unwrap().size(), 2);
+    assert!(instance.get_func("f").is_some());
+
+    // Lookups of the wrong kind or of missing exports return `None`.
+    assert!(instance.get_memory("answer").is_none());
+    assert!(instance.get_global("memory").is_none());
+    assert!(instance.get_func("missing").is_none());
+    assert!(instance.get_adapter("f").is_none());
+    Ok(())
+}
+
+#[test]
+fn extern_origin() -> Result<()> {
+    let store = Store::default();
+    let module = Module::new(
+        &store,
+        r#"
+            (module
+                (memory (export "m") 1)
+                (func (export "f")))
+        "#,
+    )?;
+    let a = Instance::new(&module, &[])?;
+    let b = Instance::new(&module, &[])?;
+    assert_ne!(a.id(), b.id());
+    assert!(a.id() < b.id());
+    for export in a.exports() {
+        assert_eq!(export.origin(), Some(a.id()));
+    }
+    for export in b.exports() {
+        assert_eq!(export.origin(), Some(b.id()));
+    }
+
+    // Re-exported functions report the instance that defined them.
+    let reexport = Module::new(
+        &store,
+        r#"
+            (module
+                (import "" "f" (func $f))
+                (export "f" (func $f)))
+        "#,
+    )?;
+    let c = Instance::new(&reexport, &[a.exports()[1].clone()])?;
+    assert_eq!(c.exports()[0].origin(), Some(a.id()));
+
+    // Items defined by the host have no origin.
+    let f: Extern = Func::wrap0(&store, || {}).into();
+    assert_eq!(f.origin(), None);
+    let memory: Extern = Memory::new(&store, MemoryType::new(Limits::new(1, None)))?.into();
+    assert_eq!(memory.origin(), None);
+    Ok(())
+}
+
+#[test]
+fn snapshot_and_restore_globals() -> Result<()> {
+    let store = Store::default();
+    let module = Module::new(
+        &store,
+        r#"
+            (module
+                (global (export "a") (mut i32) (i32.const 1))
+                (global (export "c") i32 (i32.const 2))
+                (global (export "b") (mut f64) (f64.const 3))
+                (func (export "bump")
+                    global.get 0
+                    i32.const 10
+                    i32.add
+                    global.set 0))
+        "#,
+    )?;
+    let instance = Instance::new(&module, &[])?;
+    let snapshot = instance.snapshot_globals();
+    assert_eq!(snapshot.len(), 2);
+
+    instance.get_func("bump").unwrap().call(&[])?;
+    instance.get_global("b").unwrap().set(Val::from(4.5f64))?;
+    assert_eq!(instance.get_global("a").unwrap().get().unwrap_i32(), 11);
+
+    instance.restore_globals(&snapshot)?;
+    assert_eq!(instance.get_global("a").unwrap().get().unwrap_i32(), 1);
+    assert_eq!(instance.get_global("b").unwrap().get().unwrap_f64(), 3.0);
+
+    // Mismatched values are rejected without modifying anything.
+    instance.get_func("bump").unwrap().call(&[])?;
+    assert!(instance.restore_globals(&snapshot[..1]).is_err());
+    let err = instance
+        .restore_globals(&[Val::I32(0), Val::I32(0)])
+        .unwrap_err();
+    assert_eq!(err.to_string(), "global value 1 should be f64, got i32");
+    assert_eq!(instance.get_global("a").unwrap().get().unwrap_i32(), 11);
+    Ok(())
+}
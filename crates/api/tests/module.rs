@@ -0,0 +1,49 @@
+use anyhow::Result;
+use wasmtime::*;
+
+#[test]
+fn adapters() -> Result<()> {
+    let mut config = Config::new();
+    config.wasm_interface_types(true).wasm_reference_types(true);
+    let store = Store::new(&Engine::new(&config));
+    let module = Module::from_file(&store, "../../examples/string-to-memory.wasm")?;
+
+    let adapters = module.adapters();
+    assert_eq!(adapters.len(), 2);
+    assert_eq!(adapters[0].0, "set");
+    assert_eq!(adapters[0].1.params(), &[ValType::String]);
+    assert!(adapters[0].1.results().is_empty());
+    assert_eq!(adapters[1].0, "get");
+    assert!(adapters[1].1.params().is_empty());
+    assert_eq!(adapters[1].1.results(), &[ValType::String]);
+
+    let module = Module::new(&store, "(module (func (export \"f\")))")?;
+    assert!(module.adapters().is_empty());
+    Ok(())
+}
+
+#[test]
+fn missing_imports() -> Result<()> {
+    let store = Store::default();
+    let module = Module::new(
+        &store,
+        r#"
+            (module
+                (import "env" "f" (func))
+                (import "env" "g" (global i32)))
+        "#,
+    )?;
+    let f = Func::wrap0(&store, || {});
+
+    let missing = module.missing_imports(&[f.clone().into()]);
+    assert_eq!(missing.len(), 1);
+    assert_eq!(missing[0].name(), "g");
+
+    // A provided extern of the wrong type doesn't count.
+    let missing = module.missing_imports(&[f.clone().into(), f.into()]);
+    assert_eq!(missing.len(), 1);
+    assert_eq!(missing[0].name(), "g");
+
+    assert_eq!(module.missing_imports(&[]).len(), 2);
+    Ok(())
+}
@@ -0,0 +1,303 @@
+use anyhow::Result;
+use wasmtime::*;
+
+#[test]
+fn registered_signature_count() -> Result<()> {
+    let store = Store::default();
+    let before = store.registered_signature_count();
+
+    Module::new(
+        &store,
+        r#"
+            (module
+                (func (param i32))
+                (func (param i64) (result i64) local.get 0)
+                (func (param f32 f64))
+            )
+        "#,
+    )?;
+    let after = store.registered_signature_count();
+    assert_eq!(after, before + 3);
+
+    // Signatures already registered are shared.
+    Module::new(&store, "(module (func (param i32)))")?;
+    assert_eq!(store.registered_signature_count(), after);
+    Ok(())
+}
+
+#[test]
+fn on_trap() -> Result<()> {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    let store = Store::default();
+    let seen = Rc::new(RefCell::new(Vec::new()));
+    let seen2 = seen.clone();
+    store.on_trap(move |trap| seen2.borrow_mut().push(trap.message().to_string()));
+
+    let module = Module::new(
+        &store,
+        r#"
+            (module
+                (func (export "ok"))
+                (func (export "trap") unreachable))
+        "#,
+    )?;
+    let instance = Instance::new(&module, &[])?;
+
+    instance.exports()[0].func().unwrap().call(&[])?;
+    assert!(seen.borrow().is_empty());
+
+    let trap = instance.exports()[1].func().unwrap().call(&[]).unwrap_err();
+    assert_eq!(seen.borrow().len(), 1);
+    assert_eq!(seen.borrow()[0], trap.message());
+    Ok(())
+}
+
+#[test]
+fn memory_limit() -> Result<()> {
+    const PAGE: usize = 0x10000;
+
+    let store = Store::default();
+    store.set_memory_limit(4 * PAGE);
+    let ty = |min| MemoryType::new(Limits::new(min, None));
+
+    let a = Memory::new(&store, ty(2))?;
+    let b = Memory::new(&store, ty(1))?;
+    assert_eq!(store.memory_usage(), 3 * PAGE);
+
+    // Together the two memories may only grow by one more page.
+    a.grow(1)?;
+    assert!(b.grow(1).is_err());
+    assert_eq!(b.size(), 1);
+    assert!(Memory::new(&store, ty(1)).is_err());
+    assert_eq!(store.memory_usage(), 4 * PAGE);
+
+    // Dropping a memory returns its pages to the store.
+    drop(a);
+    assert_eq!(store.memory_usage(), PAGE);
+    b.grow(3)?;
+    assert_eq!(store.memory_usage(), 4 * PAGE);
+    Ok(())
+}
+
+#[test]
+fn memory_limit_counts_instance_memories() -> Result<()> {
+    const PAGE: usize = 0x10000;
+
+    let store = Store::default();
+    store.set_memory_limit(3 * PAGE);
+    let module = Module::new(&store, r#"(module (memory (export "m") 2))"#)?;
+
+    let instance = Instance::new(&module, &[])?;
+    assert_eq!(store.memory_usage(), 2 * PAGE);
+    assert!(Instance::new(&module, &[]).is_err());
+
+    // Growing the instance's memory is checked against the limit as well.
+    let memory = instance.exports()[0].memory().unwrap().clone();
+    memory.grow(1)?;
+    assert_eq!(store.memory_usage(), 3 * PAGE);
+    assert!(memory.grow(1).is_err());
+    assert!(Memory::new(&store, MemoryType::new(Limits::new(1, None))).is_err());
+
+    drop(instance);
+    drop(memory);
+    assert_eq!(store.memory_usage(), 0);
+    Instance::new(&module, &[])?;
+    Ok(())
+}
+
+#[test]
+fn memory_limit_applies_to_wasm_memory_grow() -> Result<()> {
+    const PAGE: usize = 0x10000;
+
+    let store = Store::default();
+    store.set_memory_limit(3 * PAGE);
+    let module = Module::new(
+        &store,
+        r#"
+            (module
+                (memory (export "m") 1)
+                (func (export "grow") (param i32) (result i32)
+                    local.get 0
+                    memory.grow))
+        "#,
+    )?;
+    let instance = Instance::new(&module, &[])?;
+    let grow = instance.get_export("grow").unwrap().func().unwrap().clone();
+    let grow = grow.get1::<i32, i32>()?;
+
+    // Pages grown by wasm count towards the limit like any others.
+    assert_eq!(grow(1)?, 1);
+    assert_eq!(store.memory_usage(), 2 * PAGE);
+    assert_eq!(grow(2)?, -1);
+    assert_eq!(store.memory_usage(), 2 * PAGE);
+    assert!(Memory::new(&store, MemoryType::new(Limits::new(2, None))).is_err());
+    assert_eq!(grow(1)?, 2);
+    assert_eq!(store.memory_usage(), 3 * PAGE);
+    assert_eq!(grow(1)?, -1);
+
+    // Growing by zero pages always succeeds.
+    assert_eq!(grow(0)?, 3);
+
+    drop(grow);
+    drop(instance);
+    assert_eq!(store.memory_usage(), 0);
+    Ok(())
+}
+
+#[test]
+fn gc_keeps_rooted_refs() -> Result<()> {
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    struct Counted(Rc<Cell<usize>>);
+
+    impl Drop for Counted {
+        fn drop(&mut self) {
+            self.0.set(self.0.get() + 1);
+        }
+    }
+
+    let mut config = Config::new();
+    config.wasm_reference_types(true);
+    let store = Store::new(&Engine::new(&config));
+    let drops = Rc::new(Cell::new(0));
+    let ty = GlobalType::new(ValType::AnyRef, Mutability::Var);
+
+    // Overwritten references stay alive until the next collection.
+    let init = AnyRef::new(Box::new(Counted(drops.clone())));
+    let global = Global::new(&store, ty, Val::AnyRef(init))?;
+    for _ in 0..10 {
+        global.set(Val::AnyRef(AnyRef::new(Box::new(Counted(drops.clone())))))?;
+    }
+    assert_eq!(drops.get(), 0);
+    store.gc();
+
+    // Only the value still stored in the global survives.
+    assert_eq!(drops.get(), 10);
+    let r = global.get().anyref().expect("expected an anyref");
+    assert!(r.data().downcast_ref::<Counted>().is_some());
+    drop(r);
+
+    // A reference copied into another global by wasm survives as well.
+    let module = Module::new(
+        &store,
+        r#"
+            (module
+                (import "" "g" (global $g (mut anyref)))
+                (global $copy (export "copy") (mut anyref) (ref.null))
+                (func (export "copy_g")
+                    global.get $g
+                    global.set $copy))
+        "#,
+    )?;
+    let instance = Instance::new(&module, &[global.clone().into()])?;
+    let copy_g = instance.get_export("copy_g").unwrap().func().unwrap();
+    copy_g.call(&[])?;
+    global.set(Val::AnyRef(AnyRef::Null))?;
+    store.gc();
+    assert_eq!(drops.get(), 10);
+    let copy = instance.get_export("copy").unwrap().global().unwrap();
+    let r = copy.get().anyref().expect("expected an anyref");
+    assert!(r.data().downcast_ref::<Counted>().is_some());
+    drop(r);
+
+    // Once the instance holding the copy is gone it's garbage too.
+    drop(instance);
+    assert_eq!(drops.get(), 10);
+    store.gc();
+    assert_eq!(drops.get(), 11);
+    Ok(())
+}
+
+#[test]
+fn live_instances_and_externs() -> Result<()> {
+    let store = Store::default();
+    assert_eq!(store.live_instances(), 0);
+    assert_eq!(store.live_externs(), 0);
+
+    let module = Module::new(
+        &store,
+        r#"
+            (module
+                (import "" "f" (func))
+                (memory (export "m") 1))
+        "#,
+    )?;
+    let f = Func::wrap0(&store, || {});
+    let memory = Memory::new(&store, MemoryType::new(Limits::new(1, None)))?;
+    assert_eq!(store.live_externs(), 2);
+    drop(memory);
+    assert_eq!(store.live_externs(), 1);
+
+    let instance = Instance::new(&module, &[f.clone().into()])?;
+    assert_eq!(store.live_instances(), 1);
+
+    // Exports keep their instance alive, and instances keep their imports
+    // alive.
+    let export = instance.exports()[0].clone();
+    drop(instance);
+    drop(f);
+    assert_eq!(store.live_instances(), 1);
+    assert_eq!(store.live_externs(), 1);
+    drop(export);
+    assert_eq!(store.live_instances(), 0);
+    assert_eq!(store.live_externs(), 0);
+    Ok(())
+}
+
+#[test]
+fn run() -> Result<()> {
+    let store = Store::default();
+    let double = Func::wrap1(&store, |x: i32| x * 2);
+    let instance = store.run(
+        r#"
+            (module
+                (import "" "double" (func $double (param i32) (result i32)))
+                (func (export "run") (result i32)
+                    i32.const 21
+                    call $double))
+        "#,
+        &[double.into()],
+    )?;
+    let run = instance.get_export("run").unwrap().func().unwrap();
+    assert_eq!(run.call(&[])?[0].unwrap_i32(), 42);
+
+    let binary = wat::parse_str("(module (func (export \"f\")))")?;
+    let instance = store.run(&binary, &[])?;
+    assert!(instance.get_export("f").is_some());
+
+    assert!(store.run("(module (func", &[]).is_err());
+    Ok(())
+}
+
+#[test]
+fn instance_limit() -> Result<()> {
+    let store = Store::default();
+    let module = Module::new(&store, "(module)")?;
+    let memory = Memory::new(&store, MemoryType::new(Limits::new(1, None)))?;
+    store.set_instance_limit(3);
+
+    // The memory counts towards the limit too.
+    let a = Instance::new(&module, &[])?;
+    let _b = Instance::new(&module, &[])?;
+    let err = Instance::new(&module, &[]).err().unwrap();
+    assert_eq!(
+        err.to_string(),
+        "store already holds its limit of 3 instances and externs"
+    );
+    assert!(Memory::new(&store, MemoryType::new(Limits::new(1, None))).is_err());
+
+    // Host functions don't count towards the limit.
+    let f = Func::wrap0(&store, || {});
+    f.call(&[])?;
+    assert_eq!(store.live_externs(), 2);
+
+    // Dropping instances makes room again.
+    drop(a);
+    drop(memory);
+    Instance::new(&module, &[])?;
+    Memory::new(&store, MemoryType::new(Limits::new(1, None)))?;
+    Ok(())
+}
@@ -1,5 +1,4 @@
 use anyhow::Result;
-use std::panic::{self, AssertUnwindSafe};
 use std::rc::Rc;
 use wasmtime::*;
 
@@ -33,6 +32,7 @@ fn test_trap_return() -> Result<()> {
     let e = run_func.call(&[]).err().expect("error calling function");
 
     assert_eq!(e.message(), "test 123");
+    assert_eq!(e.kind(), TrapKind::HostError);
 
     Ok(())
 }
@@ -281,21 +281,33 @@ fn rust_panic_import() -> Result<()> {
             Func::wrap0(&store, || panic!("this is another panic")).into(),
         ],
     )?;
+    // Panics in `Callable`s are turned into traps...
     let func = instance.exports()[0].func().unwrap().clone();
-    let err = panic::catch_unwind(AssertUnwindSafe(|| {
-        drop(func.call(&[]));
-    }))
-    .unwrap_err();
-    assert_eq!(err.downcast_ref::<&'static str>(), Some(&"this is a panic"));
+    let trap = func.call(&[]).unwrap_err();
+    assert_eq!(trap.message(), "host function panicked: this is a panic");
+    assert_eq!(trap.kind(), TrapKind::HostError);
 
+    // ... including when called directly from the host ...
+    let sig = FuncType::new(Box::new([]), Box::new([]));
+    let trap = Func::new(&store, sig, Rc::new(Panic))
+        .call(&[])
+        .unwrap_err();
+    assert_eq!(trap.message(), "host function panicked: this is a panic");
+
+    // ... and so are panics in wrapped closures.
     let func = instance.exports()[1].func().unwrap().clone();
-    let err = panic::catch_unwind(AssertUnwindSafe(|| {
-        drop(func.call(&[]));
-    }))
-    .unwrap_err();
+    let trap = func.call(&[]).unwrap_err();
     assert_eq!(
-        err.downcast_ref::<&'static str>(),
-        Some(&"this is another panic")
+        trap.message(),
+        "host function panicked: this is another panic"
+    );
+    assert_eq!(trap.kind(), TrapKind::HostError);
+    let trap = Func::wrap0(&store, || panic!("this is another panic"))
+        .call(&[])
+        .unwrap_err();
+    assert_eq!(
+        trap.message(),
+        "host function panicked: this is another panic"
     );
     Ok(())
 }
@@ -323,20 +335,17 @@ fn rust_panic_start_function() -> Result<()> {
     let module = Module::new(&store, &binary)?;
     let sig = FuncType::new(Box::new([]), Box::new([]));
     let func = Func::new(&store, sig, Rc::new(Panic));
-    let err = panic::catch_unwind(AssertUnwindSafe(|| {
-        drop(Instance::new(&module, &[func.into()]));
-    }))
-    .unwrap_err();
-    assert_eq!(err.downcast_ref::<&'static str>(), Some(&"this is a panic"));
+    let err = Instance::new(&module, &[func.into()]).err().unwrap();
+    assert_eq!(
+        err.downcast_ref::<Trap>().unwrap().message(),
+        "host function panicked: this is a panic"
+    );
 
     let func = Func::wrap0(&store, || panic!("this is another panic"));
-    let err = panic::catch_unwind(AssertUnwindSafe(|| {
-        drop(Instance::new(&module, &[func.into()]));
-    }))
-    .unwrap_err();
+    let err = Instance::new(&module, &[func.into()]).err().unwrap();
     assert_eq!(
-        err.downcast_ref::<&'static str>(),
-        Some(&"this is another panic")
+        err.downcast_ref::<Trap>().unwrap().message(),
+        "host function panicked: this is another panic"
     );
     Ok(())
 }
@@ -402,3 +411,74 @@ fn call_signature_mismatch() -> Result<()> {
     );
     Ok(())
 }
+
+#[test]
+fn trap_kind() -> Result<()> {
+    let store = Store::default();
+    let wat = r#"
+        (module
+            (func (export "unreachable") unreachable)
+            (func (export "div") (param i32) (result i32)
+                i32.const 1
+                local.get 0
+                i32.div_u)
+            (memory 1)
+            (func (export "load") (param i32) (result i32)
+                local.get 0
+                i32.load)
+        )
+    "#;
+
+    let module = Module::new(&store, wat)?;
+    let instance = Instance::new(&module, &[])?;
+    let exports = instance.exports();
+
+    let e = exports[0].func().unwrap().call(&[]).unwrap_err();
+    assert_eq!(e.kind(), TrapKind::Unreachable);
+    let e = exports[1].func().unwrap().call(&[Val::I32(0)]).unwrap_err();
+    assert_eq!(e.kind(), TrapKind::IntegerDivisionByZero);
+    let e = exports[2]
+        .func()
+        .unwrap()
+        .call(&[Val::I32(65536)])
+        .unwrap_err();
+    assert_eq!(e.kind(), TrapKind::MemoryOutOfBounds);
+    Ok(())
+}
+
+#[test]
+fn max_wasm_stack() -> Result<()> {
+    let wat = r#"
+        (module
+            (func $rec (export "rec") (param i32)
+                local.get 0
+                if
+                    local.get 0
+                    i32.const 1
+                    i32.sub
+                    call $rec
+                end))
+    "#;
+
+    // A recursion which comfortably fits in the default limit ...
+    let store = Store::default();
+    let module = Module::new(&store, wat)?;
+    let instance = Instance::new(&module, &[])?;
+    let rec = instance.exports()[0].func().unwrap().get1::<i32, ()>()?;
+    rec(10_000)?;
+
+    // ... traps cleanly once the limit is lowered.
+    let mut config = Config::new();
+    config.max_wasm_stack(64 * 1024);
+    let store = Store::new(&Engine::new(&config));
+    let module = Module::new(&store, wat)?;
+    let instance = Instance::new(&module, &[])?;
+    let rec = instance.exports()[0].func().unwrap().get1::<i32, ()>()?;
+    let e = rec(10_000).err().expect("error calling function");
+    assert_eq!(e.kind(), TrapKind::StackOverflow);
+    assert!(e.message().contains("call stack exhausted"));
+
+    // The limit is reset after the trap, so shallower calls still work.
+    rec(10)?;
+    Ok(())
+}
@@ -0,0 +1,126 @@
+use wasmtime::*;
+
+#[test]
+fn nulls() -> anyhow::Result<()> {
+    assert!(Val::null().is_null());
+    assert!(Val::null_anyref().is_null());
+    assert!(Val::null_funcref().is_null());
+    assert_eq!(Val::null_anyref().ty(), ValType::AnyRef);
+
+    let store = Store::default();
+    assert!(!Val::FuncRef(Some(Func::wrap0(&store, || {}))).is_null());
+    assert!(!Val::AnyRef(AnyRef::new(Box::new(1))).is_null());
+    assert!(!Val::I32(0).is_null());
+
+    // null funcrefs can be stored in tables of functions
+    let ty = TableType::new(ValType::FuncRef, Limits::new(1, None));
+    let table = Table::new(&store, ty, Val::null_funcref())?;
+    assert!(table.get(0).unwrap().is_null());
+    Ok(())
+}
+
+#[test]
+fn display() {
+    assert_eq!(Val::I32(-1).to_string(), "-1");
+    assert_eq!(Val::I64(1 << 40).to_string(), "1099511627776");
+    assert_eq!(Val::F32(1.5f32.to_bits()).to_string(), "1.5");
+    assert_eq!(Val::F64((-0.25f64).to_bits()).to_string(), "-0.25");
+    assert_eq!(
+        Val::V128(0xff).to_string(),
+        "0x000000000000000000000000000000ff"
+    );
+    assert_eq!(Val::S8(-8).to_string(), "-8");
+    assert_eq!(Val::S16(-16).to_string(), "-16");
+    assert_eq!(Val::S32(-32).to_string(), "-32");
+    assert_eq!(Val::S64(-64).to_string(), "-64");
+    assert_eq!(Val::U8(8).to_string(), "8");
+    assert_eq!(Val::U16(16).to_string(), "16");
+    assert_eq!(Val::U32(32).to_string(), "32");
+    assert_eq!(Val::U64(64).to_string(), "64");
+    assert_eq!(
+        Val::String("hi \"x\"".to_string()).to_string(),
+        r#""hi \"x\"""#
+    );
+
+    assert_eq!(Val::null().to_string(), "anyref(null)");
+    assert_eq!(Val::null_funcref().to_string(), "funcref(null)");
+    let anyref = Val::AnyRef(AnyRef::new(Box::new(1))).to_string();
+    assert!(anyref.starts_with("anyref(0x"), "{}", anyref);
+
+    let store = Store::default();
+    let ty = FuncType::new(Box::new([]), Box::new([]));
+    let host = Val::FuncRef(Some(Func::noop(&store, ty)));
+    assert_eq!(host.to_string(), "funcref(host)");
+    let wrapped = Val::FuncRef(Some(Func::wrap0(&store, || {}))).to_string();
+    assert!(wrapped.starts_with("funcref(0x"), "{}", wrapped);
+}
+
+#[test]
+fn size_bytes() {
+    assert_eq!(ValType::I32.size_bytes(), Some(4));
+    assert_eq!(ValType::I64.size_bytes(), Some(8));
+    assert_eq!(ValType::F32.size_bytes(), Some(4));
+    assert_eq!(ValType::F64.size_bytes(), Some(8));
+    assert_eq!(ValType::V128.size_bytes(), Some(16));
+    assert_eq!(ValType::S8.size_bytes(), Some(1));
+    assert_eq!(ValType::U8.size_bytes(), Some(1));
+    assert_eq!(ValType::S16.size_bytes(), Some(2));
+    assert_eq!(ValType::U16.size_bytes(), Some(2));
+    assert_eq!(ValType::S32.size_bytes(), Some(4));
+    assert_eq!(ValType::U32.size_bytes(), Some(4));
+    assert_eq!(ValType::S64.size_bytes(), Some(8));
+    assert_eq!(ValType::U64.size_bytes(), Some(8));
+    assert_eq!(ValType::String.size_bytes(), None);
+    assert_eq!(ValType::AnyRef.size_bytes(), None);
+    assert_eq!(ValType::FuncRef.size_bytes(), None);
+}
+
+#[test]
+fn v128_lanes() {
+    let bytes = [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15];
+    let v = Val::v128_from_bytes(bytes);
+    assert_eq!(v.unwrap_v128(), 0x0f0e0d0c_0b0a0908_07060504_03020100);
+    assert_eq!(v.v128_bytes(), bytes);
+    assert_eq!(
+        v.v128_lanes_u32(),
+        [0x03020100, 0x07060504, 0x0b0a0908, 0x0f0e0d0c]
+    );
+    assert_eq!(
+        v.v128_lanes_u64(),
+        [0x07060504_03020100, 0x0f0e0d0c_0b0a0908]
+    );
+
+    let lanes = [1, 2, 0xdead_beef, u32::max_value()];
+    let v = Val::v128_from_lanes_u32(lanes);
+    assert_eq!(v.v128_lanes_u32(), lanes);
+    assert_eq!(
+        Val::v128_from_bytes(v.v128_bytes()).unwrap_v128(),
+        v.unwrap_v128()
+    );
+
+    let lanes = [1.5, -0.0, f32::INFINITY, 3.25];
+    let v = Val::v128_from_lanes_f32(lanes);
+    assert_eq!(v.v128_lanes_f32(), lanes);
+    assert_eq!(v.v128_lanes_u32()[1], (-0.0f32).to_bits());
+}
+
+#[test]
+fn try_into_primitives() -> anyhow::Result<()> {
+    use std::convert::TryInto;
+
+    let x: i32 = Val::I32(-7).try_into()?;
+    assert_eq!(x, -7);
+    let x: u64 = Val::U64(u64::max_value()).try_into()?;
+    assert_eq!(x, u64::max_value());
+    let x: f64 = Val::from(1.5f64).try_into()?;
+    assert_eq!(x, 1.5);
+    let x: String = Val::from("hi").try_into()?;
+    assert_eq!(x, "hi");
+
+    let err = TryInto::<i32>::try_into(Val::I64(1)).unwrap_err();
+    assert_eq!(err.to_string(), "expected i32 value, found i64");
+    let err = TryInto::<f32>::try_into(Val::null()).unwrap_err();
+    assert_eq!(err.to_string(), "expected f32 value, found anyref");
+    assert!(TryInto::<u32>::try_into(Val::I32(1)).is_err());
+    Ok(())
+}
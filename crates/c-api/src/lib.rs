@@ -1687,10 +1687,12 @@ pub unsafe extern "C" fn wasm_memory_new(
     store: *mut wasm_store_t,
     mt: *const wasm_memorytype_t,
 ) -> *mut wasm_memory_t {
-    let memory = HostRef::new(Memory::new(
-        &(*store).store.borrow(),
-        (*mt).memorytype.clone(),
-    ));
+    let memory = HostRef::new(
+        match Memory::new(&(*store).store.borrow(), (*mt).memorytype.clone()) {
+            Ok(m) => m,
+            Err(_) => return ptr::null_mut(),
+        },
+    );
     let m = Box::new(wasm_memory_t {
         ext: wasm_extern_t {
             which: ExternHost::Memory(memory),
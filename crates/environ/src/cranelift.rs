@@ -255,13 +255,15 @@ fn compile(
                 context.func.collect_debug_info();
             }
 
+            let mut func_env = FuncEnvironment::new(isa.frontend_config(), module);
             func_translator.translate(
                 module_translation,
                 input.data,
                 input.module_offset,
                 &mut context.func,
-                &mut FuncEnvironment::new(isa.frontend_config(), module),
+                &mut func_env,
             )?;
+            func_env.set_stack_limit(&mut context.func);
 
             let mut code_buf: Vec<u8> = Vec::new();
             let mut reloc_sink = RelocSink::new(func_index);
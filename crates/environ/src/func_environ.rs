@@ -172,6 +172,27 @@ impl<'module_environment> FuncEnvironment<'module_environment> {
         })
     }
 
+    /// Sets up `func` to check, in its prologue, that the stack pointer
+    /// doesn't go below the `stack_limit` of the `VMInterrupts` pointed to by
+    /// its vmctx, trapping with a stack overflow otherwise.
+    pub(crate) fn set_stack_limit(&mut self, func: &mut Function) {
+        let pointer_type = self.pointer_type();
+        let vmctx = self.vmctx(func);
+        let interrupts = func.create_global_value(ir::GlobalValueData::Load {
+            base: vmctx,
+            offset: Offset32::new(i32::try_from(self.offsets.vmctx_interrupts()).unwrap()),
+            global_type: pointer_type,
+            readonly: true,
+        });
+        let stack_limit = func.create_global_value(ir::GlobalValueData::Load {
+            base: interrupts,
+            offset: Offset32::new(i32::from(self.offsets.vminterrupts_stack_limit())),
+            global_type: pointer_type,
+            readonly: false,
+        });
+        func.stack_limit = Some(stack_limit);
+    }
+
     fn get_memory_grow_sig(&mut self, func: &mut Function) -> ir::SigRef {
         let sig = self.memory_grow_sig.unwrap_or_else(|| {
             func.import_signature(Signature {
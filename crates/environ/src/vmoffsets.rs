@@ -251,11 +251,27 @@ impl VMOffsets {
     }
 }
 
+/// Offsets for `VMInterrupts`.
+impl VMOffsets {
+    /// The offset of the `stack_limit` field.
+    #[allow(clippy::erasing_op)]
+    pub fn vminterrupts_stack_limit(&self) -> u8 {
+        0 * self.pointer_size
+    }
+}
+
 /// Offsets for `VMContext`.
 impl VMOffsets {
+    /// The offset of the `*const VMInterrupts` pointer.
+    pub fn vmctx_interrupts(&self) -> u32 {
+        0
+    }
+
     /// The offset of the `signature_ids` array.
     pub fn vmctx_signature_ids_begin(&self) -> u32 {
-        0
+        self.vmctx_interrupts()
+            .checked_add(u32::from(self.pointer_size))
+            .unwrap()
     }
 
     /// The offset of the `tables` array.
@@ -12,7 +12,7 @@
 
 pub mod dummy;
 
-use dummy::dummy_imports;
+use dummy::{dummy_imports, DummyPolicy};
 use std::sync::atomic::{AtomicUsize, Ordering::SeqCst};
 use wasmtime::*;
 
@@ -72,7 +72,7 @@ pub fn instantiate_with_config(wasm: &[u8], config: Config) {
         Err(_) => return,
     };
 
-    let imports = match dummy_imports(&store, module.imports()) {
+    let imports = match dummy_imports(&store, module.imports(), DummyPolicy::Zero) {
         Ok(imps) => imps,
         Err(_) => {
             // There are some value types that we can't synthesize a
@@ -154,7 +154,7 @@ pub fn differential_execution(
         // in and with what values. Like the results of exported functions,
         // calls to imports should also yield the same values for each
         // configuration, and we should assert that.
-        let imports = match dummy_imports(&store, module.imports()) {
+        let imports = match dummy_imports(&store, module.imports(), DummyPolicy::Zero) {
             Ok(imps) => imps,
             Err(e) => {
                 // There are some value types that we can't synthesize a
@@ -348,7 +348,11 @@ pub fn make_api_calls(api: crate::generators::api::ApiCalls) {
                     None => continue,
                 };
 
-                let imports = match dummy_imports(store.as_ref().unwrap(), module.imports()) {
+                let imports = match dummy_imports(
+                    store.as_ref().unwrap(),
+                    module.imports(),
+                    DummyPolicy::Zero,
+                ) {
                     Ok(imps) => imps,
                     Err(_) => {
                         // There are some value types that we can't synthesize a
@@ -6,22 +6,168 @@ use wasmtime::{
     MemoryType, Store, Table, TableType, Trap, Val, ValType,
 };
 
-/// Create a set of dummy functions/globals/etc for the given imports.
-pub fn dummy_imports(store: &Store, import_tys: &[ImportType]) -> Result<Vec<Extern>, Trap> {
+/// How initial values are chosen for dummy globals, tables and memories.
+///
+/// Reference-typed values, including all table elements, are always null.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DummyPolicy {
+    /// Use zero for numbers and leave memory zeroed.
+    Zero,
+    /// Use the maximum value of each numeric type and fill memory with `0xff`.
+    Max,
+    /// Use deterministic pseudo-random values and memory contents derived from
+    /// the given seed.
+    Random(u64),
+}
+
+impl Default for DummyPolicy {
+    fn default() -> DummyPolicy {
+        DummyPolicy::Zero
+    }
+}
+
+/// Produces the values for a [`DummyPolicy`].
+///
+/// This is shared across all imports created at once, so that `Random`
+/// doesn't hand every import the same value.
+struct Filler {
+    policy: DummyPolicy,
+    state: u64,
+}
+
+impl Filler {
+    fn new(policy: DummyPolicy) -> Filler {
+        let state = match policy {
+            DummyPolicy::Random(seed) => seed,
+            _ => 0,
+        };
+        Filler { policy, state }
+    }
+
+    /// splitmix64, which is plenty for picking fuzz inputs.
+    fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    fn value(&mut self, val_ty: &ValType) -> Result<Val, Trap> {
+        let bits = match self.policy {
+            DummyPolicy::Zero => return dummy_value(val_ty),
+            DummyPolicy::Max => None,
+            DummyPolicy::Random(_) => Some(self.next_u64()),
+        };
+        Ok(match (val_ty, bits) {
+            (ValType::I32, None) => Val::I32(i32::max_value()),
+            (ValType::I64, None) => Val::I64(i64::max_value()),
+            (ValType::F32, None) => Val::F32(std::f32::MAX.to_bits()),
+            (ValType::F64, None) => Val::F64(std::f64::MAX.to_bits()),
+            (ValType::S8, None) => Val::S8(i8::max_value()),
+            (ValType::U8, None) => Val::U8(u8::max_value()),
+            (ValType::S16, None) => Val::S16(i16::max_value()),
+            (ValType::U16, None) => Val::U16(u16::max_value()),
+            (ValType::S32, None) => Val::S32(i32::max_value()),
+            (ValType::U32, None) => Val::U32(u32::max_value()),
+            (ValType::S64, None) => Val::S64(i64::max_value()),
+            (ValType::U64, None) => Val::U64(u64::max_value()),
+            (ValType::I32, Some(bits)) => Val::I32(bits as i32),
+            (ValType::I64, Some(bits)) => Val::I64(bits as i64),
+            (ValType::F32, Some(bits)) => Val::F32(bits as u32),
+            (ValType::F64, Some(bits)) => Val::F64(bits),
+            (ValType::S8, Some(bits)) => Val::S8(bits as i8),
+            (ValType::U8, Some(bits)) => Val::U8(bits as u8),
+            (ValType::S16, Some(bits)) => Val::S16(bits as i16),
+            (ValType::U16, Some(bits)) => Val::U16(bits as u16),
+            (ValType::S32, Some(bits)) => Val::S32(bits as i32),
+            (ValType::U32, Some(bits)) => Val::U32(bits as u32),
+            (ValType::S64, Some(bits)) => Val::S64(bits as i64),
+            (ValType::U64, Some(bits)) => Val::U64(bits),
+            _ => dummy_value(val_ty)?,
+        })
+    }
+
+    fn fill(&mut self, bytes: &mut [u8]) {
+        match self.policy {
+            DummyPolicy::Zero => {}
+            DummyPolicy::Max => {
+                for b in bytes.iter_mut() {
+                    *b = 0xff;
+                }
+            }
+            DummyPolicy::Random(_) => {
+                for chunk in bytes.chunks_mut(8) {
+                    let word = self.next_u64().to_le_bytes();
+                    chunk.copy_from_slice(&word[..chunk.len()]);
+                }
+            }
+        }
+    }
+}
+
+/// Create a set of dummy functions/globals/etc for the given imports, with
+/// initial values chosen by `policy`.
+pub fn dummy_imports(
+    store: &Store,
+    import_tys: &[ImportType],
+    policy: DummyPolicy,
+) -> Result<Vec<Extern>, Trap> {
+    dummy_imports_with(store, import_tys, policy, &mut |_| None)
+}
+
+/// Create a set of dummy functions/globals/etc for the given imports, giving
+/// `custom` the first chance to provide each import.
+///
+/// Whenever `custom` returns `None` for an import, a dummy value is created
+/// for it just like [`dummy_imports`] does. This allows fuzz harnesses to
+/// inject meaningful host behavior for a few specific imports.
+pub fn dummy_imports_with(
+    store: &Store,
+    import_tys: &[ImportType],
+    policy: DummyPolicy,
+    custom: &mut impl FnMut(&ImportType) -> Option<Extern>,
+) -> Result<Vec<Extern>, Trap> {
+    let mut filler = Filler::new(policy);
     let mut imports = Vec::with_capacity(import_tys.len());
     for imp in import_tys {
-        imports.push(match imp.ty() {
-            ExternType::Func(func_ty) => Extern::Func(DummyFunc::new(&store, func_ty.clone())),
-            ExternType::Global(global_ty) => {
-                Extern::Global(dummy_global(&store, global_ty.clone())?)
-            }
-            ExternType::Table(table_ty) => Extern::Table(dummy_table(&store, table_ty.clone())?),
-            ExternType::Memory(mem_ty) => Extern::Memory(dummy_memory(&store, mem_ty.clone())),
+        imports.push(match custom(imp) {
+            Some(ext) => ext,
+            None => filled_extern(store, imp.ty(), &mut filler).map_err(|trap| {
+                Trap::new(format!(
+                    "failed to create dummy import `{}::{}` of type {:?}: {}",
+                    imp.module(),
+                    imp.name(),
+                    imp.ty(),
+                    trap.message()
+                ))
+            })?,
         });
     }
     Ok(imports)
 }
 
+/// Construct a dummy external value for the given extern type.
+pub fn dummy_extern(store: &Store, ty: &ExternType, policy: DummyPolicy) -> Result<Extern, Trap> {
+    filled_extern(store, ty, &mut Filler::new(policy))
+}
+
+fn filled_extern(store: &Store, ty: &ExternType, filler: &mut Filler) -> Result<Extern, Trap> {
+    Ok(match ty {
+        ExternType::Func(func_ty) => Extern::Func(DummyFunc::new(&store, func_ty.clone())),
+        ExternType::Global(global_ty) => {
+            Extern::Global(filled_global(&store, global_ty.clone(), filler)?)
+        }
+        ExternType::Table(table_ty) => Extern::Table(dummy_table(&store, table_ty.clone())?),
+        ExternType::Memory(mem_ty) => Extern::Memory(filled_memory(&store, mem_ty.clone(), filler)),
+        ExternType::Adapter(_) => {
+            return Err(Trap::new(
+                "dummy_extern: unsupported import type: adapter".to_string(),
+            ))
+        }
+    })
+}
+
 /// A function that doesn't do anything but return the default (zero) value for
 /// the function's type.
 #[derive(Debug)]
@@ -57,16 +203,8 @@ pub fn dummy_value(val_ty: &ValType) -> Result<Val, Trap> {
                 "dummy_value: unsupported function return type: v128".to_string(),
             ))
         }
-        ValType::AnyRef => {
-            return Err(Trap::new(
-                "dummy_value: unsupported function return type: anyref".to_string(),
-            ))
-        }
-        ValType::FuncRef => {
-            return Err(Trap::new(
-                "dummy_value: unsupported function return type: funcref".to_string(),
-            ))
-        }
+        ValType::AnyRef => Val::null_anyref(),
+        ValType::FuncRef => Val::null_funcref(),
         ValType::S8 => Val::S8(0),
         ValType::U8 => Val::U8(0),
         ValType::S16 => Val::S16(0),
@@ -88,19 +226,33 @@ pub fn dummy_values(val_tys: &[ValType]) -> Result<Vec<Val>, Trap> {
     val_tys.iter().map(dummy_value).collect()
 }
 
-/// Construct a dummy global for the given global type.
-pub fn dummy_global(store: &Store, ty: GlobalType) -> Result<Global, Trap> {
-    let val = dummy_value(ty.content())?;
+/// Construct a dummy global for the given global type, with an initial value
+/// chosen by `policy`.
+pub fn dummy_global(store: &Store, ty: GlobalType, policy: DummyPolicy) -> Result<Global, Trap> {
+    filled_global(store, ty, &mut Filler::new(policy))
+}
+
+fn filled_global(store: &Store, ty: GlobalType, filler: &mut Filler) -> Result<Global, Trap> {
+    let val = filler.value(ty.content())?;
     Ok(Global::new(store, ty, val).unwrap())
 }
 
 /// Construct a dummy table for the given table type.
+///
+/// Tables only hold references, so their elements are always null.
 pub fn dummy_table(store: &Store, ty: TableType) -> Result<Table, Trap> {
     let init_val = dummy_value(&ty.element())?;
     Ok(Table::new(store, ty, init_val).unwrap())
 }
 
-/// Construct a dummy memory for the given memory type.
-pub fn dummy_memory(store: &Store, ty: MemoryType) -> Memory {
-    Memory::new(store, ty)
+/// Construct a dummy memory for the given memory type, with contents chosen
+/// by `policy`.
+pub fn dummy_memory(store: &Store, ty: MemoryType, policy: DummyPolicy) -> Memory {
+    filled_memory(store, ty, &mut Filler::new(policy))
+}
+
+fn filled_memory(store: &Store, ty: MemoryType, filler: &mut Filler) -> Memory {
+    let memory = Memory::new(store, ty).unwrap();
+    filler.fill(unsafe { memory.data_unchecked_mut() });
+    memory
 }
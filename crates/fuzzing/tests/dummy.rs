@@ -0,0 +1,132 @@
+use std::cell::Cell;
+use std::rc::Rc;
+use wasmtime::{
+    Extern, ExternType, Func, GlobalType, ImportType, Instance, Limits, MemoryType, Module,
+    Mutability, Store, Val, ValType,
+};
+use wasmtime_fuzzing::oracles::dummy::{dummy_imports, dummy_imports_with, DummyPolicy};
+
+#[test]
+fn dummy_imports_with_overrides() -> anyhow::Result<()> {
+    let store = Store::default();
+    let module = Module::new(
+        &store,
+        r#"
+            (module
+                (import "env" "log" (func $log (param i32)))
+                (import "env" "other" (func $other (param i32)))
+                (import "env" "g" (global i32))
+                (import "env" "m" (memory 1))
+                (func (export "run")
+                    i32.const 42
+                    call $log
+                    i32.const 7
+                    call $other))
+        "#,
+    )?;
+
+    let logged = Rc::new(Cell::new(0));
+    let log = {
+        let logged = logged.clone();
+        Func::wrap1(&store, move |x: i32| logged.set(x))
+    };
+    let imports = dummy_imports_with(&store, module.imports(), DummyPolicy::Zero, &mut |import| {
+        if import.name() == "log" {
+            Some(Extern::Func(log.clone()))
+        } else {
+            None
+        }
+    })?;
+    assert_eq!(imports.len(), 4);
+    match module.imports()[2].ty() {
+        ExternType::Global(_) => assert!(imports[2].global().is_some()),
+        _ => unreachable!(),
+    }
+    assert!(imports[3].memory().is_some());
+
+    let instance = Instance::new(&module, &imports)?;
+    instance
+        .get_export("run")
+        .unwrap()
+        .func()
+        .unwrap()
+        .call(&[])?;
+    assert_eq!(logged.get(), 42);
+    Ok(())
+}
+
+#[test]
+fn dummy_imports_error_names_import() {
+    let store = Store::default();
+    let import_tys = [
+        ImportType::new(
+            "env",
+            "ok",
+            ExternType::Global(GlobalType::new(ValType::I32, Mutability::Const)),
+        ),
+        ImportType::new(
+            "env",
+            "vector",
+            ExternType::Global(GlobalType::new(ValType::V128, Mutability::Const)),
+        ),
+    ];
+    let trap = dummy_imports(&store, &import_tys, DummyPolicy::Zero)
+        .err()
+        .unwrap();
+    let message = trap.message();
+    assert!(message.contains("`env::vector`"), "{}", message);
+    assert!(message.contains("V128"), "{}", message);
+    assert!(!message.contains("env::ok"), "{}", message);
+}
+
+#[test]
+fn dummy_policies() -> anyhow::Result<()> {
+    let store = Store::default();
+    let import_tys = [
+        ImportType::new(
+            "env",
+            "g",
+            ExternType::Global(GlobalType::new(ValType::I32, Mutability::Const)),
+        ),
+        ImportType::new(
+            "env",
+            "h",
+            ExternType::Global(GlobalType::new(ValType::I64, Mutability::Const)),
+        ),
+        ImportType::new(
+            "env",
+            "m",
+            ExternType::Memory(MemoryType::new(Limits::new(1, None))),
+        ),
+    ];
+    let snapshot = |policy| -> anyhow::Result<(Val, Val, u8)> {
+        let imports = dummy_imports(&store, &import_tys, policy)?;
+        let memory = imports[2].memory().unwrap();
+        let byte = unsafe { memory.data_unchecked()[100] };
+        Ok((
+            imports[0].global().unwrap().get(),
+            imports[1].global().unwrap().get(),
+            byte,
+        ))
+    };
+
+    let (g, h, byte) = snapshot(DummyPolicy::Zero)?;
+    assert_eq!(g.unwrap_i32(), 0);
+    assert_eq!(h.unwrap_i64(), 0);
+    assert_eq!(byte, 0);
+
+    let (g, h, byte) = snapshot(DummyPolicy::Max)?;
+    assert_eq!(g.unwrap_i32(), i32::max_value());
+    assert_eq!(h.unwrap_i64(), i64::max_value());
+    assert_eq!(byte, 0xff);
+
+    // Random values are deterministic for a given seed.
+    let (g1, h1, byte1) = snapshot(DummyPolicy::Random(42))?;
+    let (g2, h2, byte2) = snapshot(DummyPolicy::Random(42))?;
+    assert_eq!(g1.unwrap_i32(), g2.unwrap_i32());
+    assert_eq!(h1.unwrap_i64(), h2.unwrap_i64());
+    assert_eq!(byte1, byte2);
+    let (g3, _, _) = snapshot(DummyPolicy::Random(43))?;
+    assert_ne!(g1.unwrap_i32(), g3.unwrap_i32());
+    Ok(())
+}
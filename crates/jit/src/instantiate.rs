@@ -7,6 +7,7 @@ use crate::compiler::Compiler;
 use crate::imports::resolve_imports;
 use crate::link::link_module;
 use crate::resolver::Resolver;
+use std::any::Any;
 use std::collections::HashMap;
 use std::io::Write;
 use std::rc::Rc;
@@ -21,7 +22,7 @@ use wasmtime_environ::{
 use wasmtime_profiling::ProfilingAgent;
 use wasmtime_runtime::{
     GdbJitImageRegistration, InstanceHandle, InstantiationError, SignatureRegistry,
-    TrapRegistration, VMFunctionBody, VMSharedSignatureIndex, VMTrampoline,
+    TrapRegistration, VMFunctionBody, VMInterrupts, VMSharedSignatureIndex, VMTrampoline,
 };
 
 /// An error condition while setting up a wasm instance, be it validation,
@@ -211,6 +212,8 @@ impl CompiledModule {
         is_bulk_memory: bool,
         resolver: &mut dyn Resolver,
         sig_registry: &SignatureRegistry,
+        host_state: Box<dyn Any>,
+        interrupts: *const VMInterrupts,
     ) -> Result<InstanceHandle, InstantiationError> {
         let data_initializers = self
             .data_initializers
@@ -231,7 +234,8 @@ impl CompiledModule {
             self.signatures.clone(),
             self.dbg_jit_registration.as_ref().map(|r| Rc::clone(&r)),
             is_bulk_memory,
-            Box::new(()),
+            host_state,
+            interrupts,
         )
     }
 
@@ -291,11 +295,14 @@ pub unsafe fn instantiate(
     debug_info: bool,
     is_bulk_memory: bool,
     profiler: Option<&Arc<Mutex<Box<dyn ProfilingAgent + Send>>>>,
+    interrupts: *const VMInterrupts,
 ) -> Result<InstanceHandle, SetupError> {
     let instance = CompiledModule::new(compiler, data, debug_info, profiler)?.instantiate(
         is_bulk_memory,
         resolver,
         compiler.signatures(),
+        Box::new(()),
+        interrupts,
     )?;
     Ok(instance)
 }
@@ -11,8 +11,8 @@ use crate::table::Table;
 use crate::traphandlers::{catch_traps, Trap};
 use crate::vmcontext::{
     VMBuiltinFunctionsArray, VMCallerCheckedAnyfunc, VMContext, VMFunctionBody, VMFunctionImport,
-    VMGlobalDefinition, VMGlobalImport, VMMemoryDefinition, VMMemoryImport, VMSharedSignatureIndex,
-    VMTableDefinition, VMTableImport, VMTrampoline,
+    VMGlobalDefinition, VMGlobalImport, VMInterrupts, VMMemoryDefinition, VMMemoryImport,
+    VMSharedSignatureIndex, VMTableDefinition, VMTableImport, VMTrampoline,
 };
 use crate::TrapRegistration;
 use crate::{ExportFunction, ExportGlobal, ExportMemory, ExportTable};
@@ -32,7 +32,7 @@ use wasmtime_environ::wasm::{
     DataIndex, DefinedFuncIndex, DefinedGlobalIndex, DefinedMemoryIndex, DefinedTableIndex,
     ElemIndex, FuncIndex, GlobalIndex, GlobalInit, MemoryIndex, SignatureIndex, TableIndex,
 };
-use wasmtime_environ::{ir, DataInitializer, Module, TableElements, VMOffsets};
+use wasmtime_environ::{ir, DataInitializer, Module, TableElements, VMOffsets, WASM_PAGE_SIZE};
 
 cfg_if::cfg_if! {
     if #[cfg(unix)] {
@@ -149,6 +149,17 @@ impl Instance {
         &*self.module
     }
 
+    /// Return a pointer to the `VMInterrupts` pointer.
+    fn interrupts_ptr(&self) -> *mut *const VMInterrupts {
+        unsafe { self.vmctx_plus_offset(self.offsets.vmctx_interrupts()) }
+    }
+
+    /// Return the `VMInterrupts` shared by all instances of this instance's
+    /// store.
+    pub(crate) fn interrupts(&self) -> &VMInterrupts {
+        unsafe { &**self.interrupts_ptr() }
+    }
+
     /// Return a pointer to the `VMSharedSignatureIndex`s.
     fn signature_ids_ptr(&self) -> *mut VMSharedSignatureIndex {
         unsafe { self.vmctx_plus_offset(self.offsets.vmctx_signature_ids_begin()) }
@@ -461,11 +472,26 @@ impl Instance {
     /// Returns `None` if memory can't be grown by the specified amount
     /// of pages.
     pub(crate) fn memory_grow(&self, memory_index: DefinedMemoryIndex, delta: u32) -> Option<u32> {
-        let result = self
+        let memory = self
             .memories
             .get(memory_index)
-            .unwrap_or_else(|| panic!("no memory for index {}", memory_index.index()))
-            .grow(delta);
+            .unwrap_or_else(|| panic!("no memory for index {}", memory_index.index()));
+
+        // Let the embedder account for the new pages first, so that it can
+        // refuse to grow the memory.
+        let hook = self.interrupts().memory_grow_hook().filter(|_| delta > 0);
+        let bytes = usize::try_from(delta)
+            .unwrap()
+            .saturating_mul(WASM_PAGE_SIZE as usize);
+        if let Some(hook) = hook {
+            if !(hook.reserve)(&*self.host_state, bytes) {
+                return None;
+            }
+        }
+        let result = memory.grow(delta);
+        if let (Some(hook), None) = (hook, result) {
+            (hook.release)(&*self.host_state, bytes);
+        }
 
         // Keep current the VMContext pointers used by compiled wasm code.
         self.set_memory(memory_index, self.memories[memory_index].vmmemory());
@@ -557,6 +583,26 @@ impl Instance {
             .set(index, val)
     }
 
+    fn table_root(&self, table_index: DefinedTableIndex, index: u32) -> Option<Rc<dyn Any>> {
+        self.tables
+            .get(table_index)
+            .unwrap_or_else(|| panic!("no table for index {}", table_index.index()))
+            .root(index)
+    }
+
+    fn table_set_rooted(
+        &self,
+        table_index: DefinedTableIndex,
+        index: u32,
+        val: VMCallerCheckedAnyfunc,
+        root: Option<Rc<dyn Any>>,
+    ) -> Result<(), ()> {
+        self.tables
+            .get(table_index)
+            .unwrap_or_else(|| panic!("no table for index {}", table_index.index()))
+            .set_rooted(index, val, root)
+    }
+
     fn alloc_layout(&self) -> Layout {
         let size = mem::size_of_val(self)
             .checked_add(usize::try_from(self.offsets.size_of_vmctx()).unwrap())
@@ -859,6 +905,13 @@ impl InstanceHandle {
     /// internally if you'd like to do so. If possible it's recommended to use
     /// the `wasmtime` crate API rather than this type since that is vetted for
     /// safety.
+    ///
+    /// The `interrupts` pointer must be non-null and remain valid for as long
+    /// as code in this instance may run.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `interrupts` is null.
     pub unsafe fn new(
         module: Arc<Module>,
         trap_registration: TrapRegistration,
@@ -870,7 +923,12 @@ impl InstanceHandle {
         dbg_jit_registration: Option<Rc<GdbJitImageRegistration>>,
         is_bulk_memory: bool,
         host_state: Box<dyn Any>,
+        interrupts: *const VMInterrupts,
     ) -> Result<Self, InstantiationError> {
+        assert!(
+            !interrupts.is_null(),
+            "instance created without a VMInterrupts"
+        );
         let tables = create_tables(&module);
         let memories = create_memories(&module)?;
 
@@ -922,6 +980,7 @@ impl InstanceHandle {
         };
         let instance = handle.instance();
 
+        *instance.interrupts_ptr() = interrupts;
         ptr::copy(
             vmshared_signatures.values().as_slice().as_ptr(),
             instance.signature_ids_ptr() as *mut VMSharedSignatureIndex,
@@ -1106,6 +1165,27 @@ impl InstanceHandle {
         self.instance().table_set(table_index, index, val)
     }
 
+    /// Get the host object kept alive by a table element, if any.
+    ///
+    /// Returns `None` if the index is out of bounds or nothing is rooted.
+    pub fn table_root(&self, table_index: DefinedTableIndex, index: u32) -> Option<Rc<dyn Any>> {
+        self.instance().table_root(table_index, index)
+    }
+
+    /// Set table element reference, keeping `root` alive for as long as the
+    /// element stays in the table.
+    ///
+    /// Returns an error if the index is out of bounds
+    pub fn table_set_rooted(
+        &self,
+        table_index: DefinedTableIndex,
+        index: u32,
+        val: VMCallerCheckedAnyfunc,
+        root: Option<Rc<dyn Any>>,
+    ) -> Result<(), ()> {
+        self.instance().table_set_rooted(table_index, index, val, root)
+    }
+
     /// Get a table defined locally within this module.
     pub fn get_defined_table(&self, index: DefinedTableIndex) -> &Table {
         self.instance().get_defined_table(index)
@@ -1116,6 +1196,14 @@ impl InstanceHandle {
         self.instance().trampolines.get(&sig).cloned()
     }
 
+    /// Returns the number of `InstanceHandle`s currently keeping the
+    /// underlying `Instance` alive.
+    ///
+    /// This is intended for debugging leaks of instances only.
+    pub fn refcount(&self) -> usize {
+        self.instance().refcount.get()
+    }
+
     /// Return a reference to the contained `Instance`.
     pub(crate) fn instance(&self) -> &Instance {
         unsafe { &*(self.instance as *const Instance) }
@@ -1385,7 +1473,8 @@ fn initialize_globals(instance: &Instance) {
                     *to = from;
                 }
                 GlobalInit::Import => panic!("locally-defined global initialized as import"),
-                GlobalInit::RefNullConst | GlobalInit::RefFunc(_) => unimplemented!(),
+                GlobalInit::RefNullConst => *(*to).as_u128_mut() = 0,
+                GlobalInit::RefFunc(_) => unimplemented!(),
             }
         }
     }
@@ -58,6 +58,16 @@ impl SignatureRegistry {
         }
     }
 
+    /// Returns the number of distinct signatures registered so far.
+    pub fn len(&self) -> usize {
+        self.inner.read().unwrap().signature2index.len()
+    }
+
+    /// Returns whether no signatures have been registered yet.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
     /// Looks up a shared signature index within this registry.
     ///
     /// Note that for this operation to be semantically correct the `idx` must
@@ -4,8 +4,11 @@
 
 use crate::vmcontext::{VMCallerCheckedAnyfunc, VMTableDefinition};
 use crate::Trap;
+use std::any::Any;
 use std::cell::RefCell;
 use std::convert::{TryFrom, TryInto};
+use std::mem;
+use std::rc::Rc;
 use wasmtime_environ::wasm::TableElementType;
 use wasmtime_environ::{ir, TablePlan, TableStyle};
 
@@ -13,6 +16,8 @@ use wasmtime_environ::{ir, TablePlan, TableStyle};
 #[derive(Debug)]
 pub struct Table {
     vec: RefCell<Vec<VMCallerCheckedAnyfunc>>,
+    /// Host objects kept alive by the element at the same index in `vec`.
+    roots: RefCell<Vec<Option<Rc<dyn Any>>>>,
     maximum: Option<u32>,
 }
 
@@ -32,6 +37,7 @@ impl Table {
                     VMCallerCheckedAnyfunc::default();
                     usize::try_from(plan.table.minimum).unwrap()
                 ]),
+                roots: RefCell::new(vec![None; usize::try_from(plan.table.minimum).unwrap()]),
                 maximum: plan.table.maximum,
             },
         }
@@ -64,6 +70,9 @@ impl Table {
             usize::try_from(new_len).unwrap(),
             VMCallerCheckedAnyfunc::default(),
         );
+        self.roots
+            .borrow_mut()
+            .resize(usize::try_from(new_len).unwrap(), None);
         Some(new_len)
     }
 
@@ -80,13 +89,40 @@ impl Table {
     ///
     /// Panics if `index` is out of bounds.
     pub fn set(&self, index: u32, func: VMCallerCheckedAnyfunc) -> Result<(), ()> {
+        self.set_rooted(index, func, None)
+    }
+
+    /// Set reference to the specified element, keeping `root` alive for as
+    /// long as the element stays in the table.
+    ///
+    /// Any root previously held by the element is released.
+    ///
+    /// Returns an error if `index` is out of bounds.
+    pub fn set_rooted(
+        &self,
+        index: u32,
+        func: VMCallerCheckedAnyfunc,
+        root: Option<Rc<dyn Any>>,
+    ) -> Result<(), ()> {
         match self.vec.borrow_mut().get_mut(index as usize) {
-            Some(slot) => {
-                *slot = func;
-                Ok(())
-            }
-            None => Err(()),
+            Some(slot) => *slot = func,
+            None => return Err(()),
         }
+        // Release the previous root outside of the `roots` borrow, since
+        // dropping it may in turn drop other instances.
+        let prev = mem::replace(&mut self.roots.borrow_mut()[index as usize], root);
+        drop(prev);
+        Ok(())
+    }
+
+    /// Returns the host object kept alive by the specified element, if any.
+    ///
+    /// Returns `None` if the index is out of bounds.
+    pub fn root(&self, index: u32) -> Option<Rc<dyn Any>> {
+        self.roots
+            .borrow()
+            .get(index as usize)
+            .and_then(|root| root.clone())
     }
 
     /// Copy `len` elements from `src_table[src_index..]` into `dst_table[dst_index..]`.
@@ -124,11 +160,15 @@ impl Table {
         // TODO(#983): investigate replacing this get/set loop with a `memcpy`.
         if dst_index <= src_index {
             for (s, d) in (srcs).zip(dsts) {
-                dst_table.set(d, src_table.get(s).unwrap()).unwrap();
+                dst_table
+                    .set_rooted(d, src_table.get(s).unwrap(), src_table.root(s))
+                    .unwrap();
             }
         } else {
             for (s, d) in srcs.rev().zip(dsts.rev()) {
-                dst_table.set(d, src_table.get(s).unwrap()).unwrap();
+                dst_table
+                    .set_rooted(d, src_table.get(s).unwrap(), src_table.root(s))
+                    .unwrap();
             }
         }
 
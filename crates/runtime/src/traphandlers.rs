@@ -185,11 +185,16 @@ pub unsafe fn wasmtime_call_trampoline(
 /// Catches any wasm traps that happen within the execution of `closure`,
 /// returning them as a `Result`.
 ///
+/// If this is the outermost call into wasm for the store owning `vmctx`, the
+/// stack limit checked by compiled wasm code is set up for the duration of
+/// the call.
+///
 /// Highly unsafe since `closure` won't have any dtors run.
 pub unsafe fn catch_traps<F>(vmctx: *mut VMContext, mut closure: F) -> Result<(), Trap>
 where
     F: FnMut(),
 {
+    let _stack_limit = (*vmctx).instance().interrupts().enter_wasm();
     return CallThreadState::new(vmctx).with(|cx| {
         RegisterSetjmp(
             cx.jmp_buf.as_ptr(),
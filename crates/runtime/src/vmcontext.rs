@@ -3,7 +3,8 @@
 
 use crate::instance::Instance;
 use std::any::Any;
-use std::{ptr, u32};
+use std::cell::Cell;
+use std::{fmt, ptr, u32};
 use wasmtime_environ::BuiltinFunctionIndex;
 
 /// An imported function.
@@ -612,6 +613,128 @@ impl VMInvokeArgument {
     }
 }
 
+/// Structure used to control the execution of wasm code, shared by all
+/// instances of a store. Each `VMContext` holds a pointer to it.
+#[derive(Debug)]
+#[repr(C)]
+pub struct VMInterrupts {
+    /// Current stack limit of the wasm code running on this thread.
+    ///
+    /// Compiled wasm code traps with a stack overflow when the stack pointer
+    /// goes below this value. It's zero, meaning no limit, while no wasm code
+    /// is running.
+    pub stack_limit: Cell<usize>,
+
+    /// The maximum number of bytes of stack which wasm code may use, counted
+    /// from the outermost call into wasm. This is only read by the host.
+    max_wasm_stack: usize,
+
+    /// Accounts for memories growing, see `VMInterrupts::set_memory_grow_hook`.
+    /// This is only read by the host.
+    memory_grow_hook: Cell<Option<MemoryGrowHook>>,
+}
+
+/// Callbacks through which the embedder accounts for the linear memory of its
+/// instances growing, whether through wasm's `memory.grow` or the host.
+#[derive(Clone, Copy)]
+pub struct MemoryGrowHook {
+    /// Called with the host state of the instance defining a memory, and the
+    /// number of bytes that memory is about to grow by. The memory doesn't
+    /// grow if this returns `false`.
+    pub reserve: fn(&dyn Any, usize) -> bool,
+
+    /// Called with the same arguments as `reserve` if the memory then fails to
+    /// grow after all, to return the reserved bytes.
+    pub release: fn(&dyn Any, usize),
+}
+
+impl fmt::Debug for MemoryGrowHook {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MemoryGrowHook").finish()
+    }
+}
+
+#[cfg(test)]
+mod test_vminterrupts {
+    use super::VMInterrupts;
+    use memoffset::offset_of;
+    use std::mem::size_of;
+    use wasmtime_environ::{Module, VMOffsets};
+
+    #[test]
+    fn check_vminterrupts_stack_limit_offset() {
+        let module = Module::new();
+        let offsets = VMOffsets::new(size_of::<*mut u8>() as u8, &module.local);
+        assert_eq!(
+            offset_of!(VMInterrupts, stack_limit),
+            usize::from(offsets.vminterrupts_stack_limit())
+        );
+    }
+}
+
+impl VMInterrupts {
+    /// Creates a new `VMInterrupts` which limits wasm code to using
+    /// `max_wasm_stack` bytes of stack.
+    pub fn new(max_wasm_stack: usize) -> Self {
+        Self {
+            stack_limit: Cell::new(0),
+            max_wasm_stack,
+            memory_grow_hook: Cell::new(None),
+        }
+    }
+
+    /// Sets the hook called whenever a memory of an instance sharing this
+    /// `VMInterrupts` grows.
+    pub fn set_memory_grow_hook(&self, hook: MemoryGrowHook) {
+        self.memory_grow_hook.set(Some(hook));
+    }
+
+    /// Returns the hook set with `VMInterrupts::set_memory_grow_hook`, if any.
+    pub(crate) fn memory_grow_hook(&self) -> Option<MemoryGrowHook> {
+        self.memory_grow_hook.get()
+    }
+
+    /// Returns the maximum number of bytes of stack which wasm code may use.
+    pub fn max_wasm_stack(&self) -> usize {
+        self.max_wasm_stack
+    }
+
+    /// Sets up the stack limit for a call into wasm.
+    ///
+    /// If no wasm code is running yet the limit is set `max_wasm_stack` bytes
+    /// below the current stack pointer, and reset once the returned guard is
+    /// dropped. Nested calls into wasm, for example from host functions called
+    /// by wasm, keep the limit set by the outermost call.
+    pub(crate) fn enter_wasm(&self) -> StackLimitGuard<'_> {
+        if self.stack_limit.get() != 0 {
+            return StackLimitGuard { interrupts: None };
+        }
+        // The address of a local is a close enough approximation of the
+        // current stack pointer.
+        let marker = 0u8;
+        let sp = &marker as *const u8 as usize;
+        // Saturating to zero just means that wasm can use the whole stack.
+        self.stack_limit.set(sp.saturating_sub(self.max_wasm_stack));
+        StackLimitGuard {
+            interrupts: Some(self),
+        }
+    }
+}
+
+/// Resets the stack limit of a `VMInterrupts` once the outermost call into
+/// wasm returns, see `VMInterrupts::enter_wasm`.
+pub(crate) struct StackLimitGuard<'a> {
+    interrupts: Option<&'a VMInterrupts>,
+}
+
+impl Drop for StackLimitGuard<'_> {
+    fn drop(&mut self) {
+        if let Some(interrupts) = self.interrupts {
+            interrupts.stack_limit.set(0);
+        }
+    }
+}
+
 /// The VM "context", which is pointed to by the `vmctx` arg in Cranelift.
 /// This has information about globals, memories, tables, and other runtime
 /// state associated with the current instance.
@@ -54,7 +54,7 @@ pub fn instantiate_spectest(store: &Store) -> HashMap<&'static str, Extern> {
     ret.insert("table", Extern::Table(table));
 
     let ty = MemoryType::new(Limits::new(1, Some(2)));
-    let memory = Memory::new(store, ty);
+    let memory = Memory::new(store, ty).unwrap();
     ret.insert("memory", Extern::Memory(memory));
 
     return ret;
@@ -26,7 +26,7 @@ fn main() -> Result<()> {
     // println!("{:#?}", module.name());
     // println!("{:#?}", module.exports());
     // println!("{:#?}", module.imports());
-    // println!("{:#?}", module.adapters());
+    println!("{:#?}", module.adapters());
 
     // After we have a compiled `Module` we can then instantiate it, creating
     // an `Instance` which we can actually poke at functions on.
@@ -39,7 +39,7 @@ fn main() -> Result<()> {
     // run it.
     println!("Extracting export 'set'...");
 
-    let params = [Val::String("Hello world".to_string())];
+    let params = [ValRef::Str("Hello world")];
     match run("set", &instance, &params) {
         // Ok(s) => println!("{:#?}", s),
         Ok(_) => (),
@@ -48,16 +48,16 @@ fn main() -> Result<()> {
 
     
     println!("Extracting export 'get'...");
-    match run("get", &instance, &[]) {
-        // Ok(s) => println!("{:#?}", s),
-        Ok(_) => (),
-        Err(e) => println!("Error occured: {}", e),
-    };
+    let get = instance
+        .get_adapter("get")
+        .ok_or(anyhow::format_err!("failed to find `get` adapter export"))?;
+    let (s,) = get.call_typed_out::<(String,)>(&[])?;
+    println!("{}", s);
 
     Ok(())
 }
 
-fn run(func_name: &str, instance: &Instance, params: &[Val]) -> Result<Vec<String>, Box<dyn Error>> {
+fn run(func_name: &str, instance: &Instance, params: &[ValRef]) -> Result<Vec<String>, Box<dyn Error>> {
     let answer = instance.get_export(&func_name)
         .and_then(|e| e.adapter())
         .ok_or(anyhow::format_err!("failed to find `run` function export"))?;
@@ -65,7 +65,7 @@ fn run(func_name: &str, instance: &Instance, params: &[Val]) -> Result<Vec<Strin
     println!("Calling export...");
     let res_string: Vec<String>;
 
-    let result = answer.call(&params)?;
+    let result = answer.call_ref(params)?;
 
     println!("Call into Func '{}' succeed", func_name);
     println!("");
@@ -82,9 +82,8 @@ fn run(func_name: &str, instance: &Instance, params: &[Val]) -> Result<Vec<Strin
 
 fn print_result(value: &Val) -> String {
     let rust_val = match value {
-        Val::I32(_) => format!("{}", value.unwrap_i32()),
-        Val::String(_) => value.unwrap_string().to_string(),
-        _ => unimplemented!("Not implemented types"),
+        Val::String(s) => s.clone(),
+        _ => value.to_string(),
     };
     println!("{}", rust_val);
     rust_val
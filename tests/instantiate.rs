@@ -4,6 +4,7 @@ use wasmtime_environ::settings;
 use wasmtime_environ::settings::Configurable;
 use wasmtime_environ::CacheConfig;
 use wasmtime_jit::{instantiate, native, CompilationStrategy, Compiler, NullResolver};
+use wasmtime_runtime::VMInterrupts;
 
 const PATH_MODULE_RS2WASM_ADD_FUNC: &str = r"tests/wat/rs2wasm-add-func.wat";
 
@@ -23,6 +24,7 @@ fn test_environ_translate() {
     let mut resolver = NullResolver {};
     let cache_config = CacheConfig::new_cache_disabled();
     let mut compiler = Compiler::new(isa, CompilationStrategy::Auto, cache_config);
+    let interrupts = VMInterrupts::new(1 << 20);
     unsafe {
         let instance = instantiate(
             &mut compiler,
@@ -34,6 +36,7 @@ fn test_environ_translate() {
             false,
             // Profiler.
             None,
+            &interrupts,
         );
         assert!(instance.is_ok());
     }